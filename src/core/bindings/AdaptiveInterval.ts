@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export interface AdaptiveInterval { min_wait_ms: number, max_wait_ms: number, current_wait_ms: number | null, }
\ No newline at end of file
@@ -1,4 +1,11 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ConfigFormat } from "./ConfigFormat";
+import type { ImportMode } from "./ImportMode";
+import type { LogLevel } from "./LogLevel";
+import type { OnceTarget } from "./OnceTarget";
+import type { ProfilePatch } from "./ProfilePatch";
+import type { ReminderPatch } from "./ReminderPatch";
 import type { ReminderState } from "./ReminderState";
+import type { Settings } from "./Settings";
 
-export type ClientCommand = { key: "AddOne", params: { value: number, } } | { key: "Add", params: { x: number, y: number, } } | { key: "SaveReminders", params: { reminders: Array<ReminderState>, } } | { key: "DelayReminder", params: { id: string, delay: number, } };
\ No newline at end of file
+export type ClientCommand = { key: "AddOne", params: { value: number, } } | { key: "Add", params: { x: number, y: number, } } | { key: "SaveReminders", params: { reminders: Array<ReminderState>, } } | { key: "CreateReminder", params: { name: string, wait_ms: number, duration_ms: number, is_active: boolean, } } | { key: "ScheduleOnce", params: { name: string, target: OnceTarget, duration_ms: number, delete_when_done: boolean, } } | { key: "UpdateReminder", params: { id: string, patch: ReminderPatch, } } | { key: "DeleteReminder", params: { id: string, } } | { key: "SetReminderActive", params: { id: string, is_active: boolean, } } | { key: "ReorderReminders", params: { ids: Array<string>, } } | { key: "Shutdown" } | { key: "DelayReminder", params: { id: string, delay: number, } } | { key: "SkipReminder", params: { id: string, } } | { key: "CompleteReminder", params: { id: string, } } | { key: "LogIntake", params: { id: string, amount_ml: number | null, } } | { key: "SetPaused", params: { paused: boolean, } } | { key: "ReloadState" } | { key: "SetLogLevel", params: { level: LogLevel, } } | { key: "UpdateSettings", params: { settings: Settings, } } | { key: "CreateProfile", params: { name: string, } } | { key: "UpdateProfile", params: { id: string, patch: ProfilePatch, } } | { key: "DeleteProfile", params: { id: string, } } | { key: "SwitchProfile", params: { id: string, } } | { key: "ApplyPreset", params: { id: string, } } | { key: "FinishOnboarding", params: { presets: Array<string> | null, } } | { key: "ExportConfig", params: { format: ConfigFormat, } } | { key: "ImportConfig", params: { content: string, mode: ImportMode, } };
\ No newline at end of file
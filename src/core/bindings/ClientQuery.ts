@@ -1,3 +1,3 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 
-export type ClientQuery = { key: "ClientGetState" } | { key: "JobGetRunning" };
\ No newline at end of file
+export type ClientQuery = { key: "ClientGetState" } | { key: "JobGetRunning" } | { key: "WebhookGetDeliveries" } | { key: "HistoryGet" } | { key: "ReminderGetSchedule" } | { key: "GetRecentLogs", params: { limit: number | null, } } | { key: "GetSettings" } | { key: "ExerciseGetLibrary" } | { key: "PresetGetCatalogue" } | { key: "IntakeGetStats", params: { days: number | null, } };
\ No newline at end of file
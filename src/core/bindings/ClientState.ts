@@ -1,4 +1,10 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { HookState } from "./HookState";
+import type { HttpApiState } from "./HttpApiState";
+import type { LogLevel } from "./LogLevel";
+import type { ProfileState } from "./ProfileState";
 import type { ReminderState } from "./ReminderState";
+import type { Settings } from "./Settings";
+import type { WebhookState } from "./WebhookState";
 
-export interface ClientState { client_uuid: string, data_path: string, reminders: Array<ReminderState>, }
\ No newline at end of file
+export interface ClientState { client_uuid: string, data_path: string, reminders: Array<ReminderState>, webhooks: Array<WebhookState>, hooks: HookState, allow_command_hooks: boolean, hook_timeout_ms: number, notifications: boolean, paused: boolean, http_api: HttpApiState, log_level: LogLevel, profiles: Array<ProfileState>, active_profile: string, settings: Settings, onboarded: boolean, }
\ No newline at end of file
@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type CoreError = { code: "storage", details: { reason: string, } } | { code: "validation", details: { field: string, reason: string, } } | { code: "not_found", details: { resource: string, id: string, } } | { code: "integration", details: { integration: string, reason: string, } } | { code: "scheduler", details: { reason: string, } } | { code: "invalid_request", details: { reason: string, } } | { code: "internal", details: { reason: string, } } | { code: "unavailable" };
\ No newline at end of file
@@ -1,3 +1,6 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { CoreError } from "./CoreError";
+import type { Exercise } from "./Exercise";
+import type { ReminderMessage } from "./ReminderMessage";
 
-export type CoreEvent = { ReminderNewStatus: { id: string, next_duration_ms: number, } };
\ No newline at end of file
+export type CoreEvent = { ReminderNewStatus: { id: string, next_duration_ms: number, message: ReminderMessage, exercises: Array<Exercise>, } } | { ReminderEnd: { id: string, } } | { ReminderIntervalAdapted: { id: string, previous_wait_ms: number, wait_ms: number, reason: string, } } | { StateChanged: { reminder_id: string | null, } } | { EngineError: { reminder_id: string | null, error: CoreError, } } | "ShuttingDown" | { Lagged: { skipped: number, } };
\ No newline at end of file
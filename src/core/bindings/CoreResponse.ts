@@ -1,4 +1,15 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 import type { ClientState } from "./ClientState";
+import type { HistoryEntry } from "./HistoryEntry";
+import type { ImportReport } from "./ImportReport";
+import type { IntakeDay } from "./IntakeDay";
+import type { Library } from "./Library";
+import type { LogRecord } from "./LogRecord";
+import type { Preset } from "./Preset";
+import type { ProfileState } from "./ProfileState";
+import type { ReminderSchedule } from "./ReminderSchedule";
+import type { ReminderState } from "./ReminderState";
+import type { Settings } from "./Settings";
+import type { WebhookDelivery } from "./WebhookDelivery";
 
-export type CoreResponse = { key: "Success", data: null } | { key: "ClientGetState", data: ClientState } | { key: "Sum", data: number };
\ No newline at end of file
+export type CoreResponse = { key: "Success", data: null } | { key: "ClientGetState", data: ClientState } | { key: "WebhookGetDeliveries", data: Array<WebhookDelivery> } | { key: "HistoryGet", data: Array<HistoryEntry> } | { key: "ReminderGetSchedule", data: Array<ReminderSchedule> } | { key: "Reminder", data: ReminderState } | { key: "Reminders", data: Array<ReminderState> } | { key: "Profile", data: ProfileState } | { key: "Settings", data: Settings } | { key: "ExerciseLibrary", data: Library } | { key: "Presets", data: Array<Preset> } | { key: "IntakeStats", data: Array<IntakeDay> } | { key: "Logs", data: Array<LogRecord> } | { key: "Config", data: string } | { key: "ConfigImported", data: ImportReport } | { key: "Sum", data: number };
\ No newline at end of file
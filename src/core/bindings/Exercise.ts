@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { BodyArea } from "./BodyArea";
+
+export interface Exercise { id: string, title: string, area: BodyArea, duration_ms: number, steps: Array<string>, }
\ No newline at end of file
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { BodyArea } from "./BodyArea";
+
+export type ExerciseSelection = { key: "Set", value: string } | { key: "Area", value: BodyArea };
\ No newline at end of file
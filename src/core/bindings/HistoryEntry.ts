@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { HistoryKind } from "./HistoryKind";
+
+export interface HistoryEntry { timestamp: string, reminder_id: string, kind: HistoryKind, }
\ No newline at end of file
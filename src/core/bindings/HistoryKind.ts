@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { HookOutput } from "./HookOutput";
+
+export type HistoryKind = { key: "ReminderStarted" } | { key: "ReminderEnded" } | { key: "Completed" } | { key: "Skipped" } | { key: "Delayed", data: { delay_ms: number, } } | { key: "HookRan", data: HookOutput } | { key: "Intake", data: { amount_ml: number, } };
\ No newline at end of file
@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export interface HookOutput { event: string, command: string, exit_code: number | null, stdout: string, stderr: string, timed_out: boolean, }
\ No newline at end of file
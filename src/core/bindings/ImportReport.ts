@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ImportConflict } from "./ImportConflict";
+
+export interface ImportReport { added: Array<string>, updated: Array<string>, removed: Array<string>, conflicts: Array<ImportConflict>, }
\ No newline at end of file
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { CoreError } from "./CoreError";
+import type { CoreEvent } from "./CoreEvent";
+import type { CoreResponse } from "./CoreResponse";
+
+export type IpcMessage = { type: "Response", data: CoreResponse } | { type: "Error", data: CoreError } | { type: "Event", data: CoreEvent };
\ No newline at end of file
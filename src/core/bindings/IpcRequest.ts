@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ClientCommand } from "./ClientCommand";
+import type { ClientQuery } from "./ClientQuery";
+import type { EventFilter } from "./EventFilter";
+
+export type IpcRequest = { type: "Query", data: ClientQuery } | { type: "Command", data: ClientCommand } | { type: "Subscribe", data: EventFilter | null };
\ No newline at end of file
@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Exercise } from "./Exercise";
+import type { ExerciseSet } from "./ExerciseSet";
+
+export interface Library { sets: Array<ExerciseSet>, exercises: Array<Exercise>, }
\ No newline at end of file
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { LogLevel } from "./LogLevel";
+
+export interface LogRecord { timestamp: string, level: LogLevel, target: string, spans: Array<string>, message: string, }
\ No newline at end of file
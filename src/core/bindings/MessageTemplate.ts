@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Rotation } from "./Rotation";
+
+export interface MessageTemplate { title: string | null, body: string | null, messages: Array<string>, rotation: Rotation, }
\ No newline at end of file
@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type OnceTarget = { key: "At", value: { time: string, date: string | null, } } | { key: "In", value: { delay_ms: number, } };
\ No newline at end of file
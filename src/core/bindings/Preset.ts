@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ExerciseSelection } from "./ExerciseSelection";
+import type { MessageTemplate } from "./MessageTemplate";
+import type { ReminderKind } from "./ReminderKind";
+
+export interface Preset { id: string, name: string, description: string, wait_ms: number, duration_ms: number, message: MessageTemplate, exercises: ExerciseSelection | null, kind: ReminderKind, }
\ No newline at end of file
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ProfileRule } from "./ProfileRule";
+
+export interface ProfilePatch { name: string | null, rules: Array<ProfileRule> | null, }
\ No newline at end of file
@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ProfileRule } from "./ProfileRule";
+import type { ReminderState } from "./ReminderState";
+
+export interface ProfileState { id: string, name: string, reminders: Array<ReminderState>, notifications: boolean, paused: boolean, rules: Array<ProfileRule>, }
\ No newline at end of file
@@ -0,0 +1,9 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { AdaptiveInterval } from "./AdaptiveInterval";
+import type { ExerciseSelection } from "./ExerciseSelection";
+import type { MessageTemplate } from "./MessageTemplate";
+import type { OneShot } from "./OneShot";
+import type { ReminderKind } from "./ReminderKind";
+import type { ReminderTiming } from "./ReminderTiming";
+
+export interface ReminderPatch { name: string | null, is_active: boolean | null, wait_ms: number | null, duration_ms: number | null, message: MessageTemplate | null, exercises: ExerciseSelection | null | null, kind: ReminderKind | null, adaptive: AdaptiveInterval | null | null, timing: ReminderTiming | null, once: OneShot | null | null, }
\ No newline at end of file
@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export interface ReminderSchedule { id: string, name: string, is_active: boolean, wait_ms: number, duration_ms: number, next_execution: string | null, }
\ No newline at end of file
@@ -1,3 +1,10 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { AdaptiveInterval } from "./AdaptiveInterval";
+import type { ExerciseSelection } from "./ExerciseSelection";
+import type { HookState } from "./HookState";
+import type { MessageTemplate } from "./MessageTemplate";
+import type { OneShot } from "./OneShot";
+import type { ReminderKind } from "./ReminderKind";
+import type { ReminderTiming } from "./ReminderTiming";
 
-export interface ReminderState { id: string, name: string, is_active: boolean, wait_ms: number, duration_ms: number, }
\ No newline at end of file
+export interface ReminderState { id: string, name: string, is_active: boolean, wait_ms: number, duration_ms: number, hooks: HookState, message: MessageTemplate, exercises: ExerciseSelection | null, kind: ReminderKind, adaptive: AdaptiveInterval | null, timing: ReminderTiming, once: OneShot | null, }
\ No newline at end of file
@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type ReminderTiming = { key: "Fixed" } | { key: "Jitter", value: { jitter_ms: number, } } | { key: "Window", value: { min_wait_ms: number, max_wait_ms: number, } };
\ No newline at end of file
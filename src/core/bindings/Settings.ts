@@ -0,0 +1,9 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { HydrationSettings } from "./HydrationSettings";
+import type { IntegrationSettings } from "./IntegrationSettings";
+import type { NewReminderDefaults } from "./NewReminderDefaults";
+import type { NotificationSettings } from "./NotificationSettings";
+import type { StartupSettings } from "./StartupSettings";
+import type { WorkingHours } from "./WorkingHours";
+
+export interface Settings { new_reminder: NewReminderDefaults, notification: NotificationSettings, startup: StartupSettings, working_hours: WorkingHours, locale: string, integrations: IntegrationSettings, hydration: HydrationSettings, }
\ No newline at end of file
@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { DeliveryStatus } from "./DeliveryStatus";
+import type { WebhookPayload } from "./WebhookPayload";
+
+export interface WebhookDelivery { id: string, webhook_id: string, url: string, payload: WebhookPayload, body: string, signature: string, status: DeliveryStatus, attempts: number, last_error: string | null, last_attempt: string | null, next_attempt: string, }
\ No newline at end of file
@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { CoreEvent } from "./CoreEvent";
+import type { ReminderMessage } from "./ReminderMessage";
+
+export interface WebhookPayload { reminder_id: string, reminder_name: string, event: string, occurred_at: string, next_execution: string | null, message: ReminderMessage | null, details: CoreEvent | null, }
\ No newline at end of file
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Weekday } from "./Weekday";
+
+export interface WorkingHours { enabled: boolean, weekdays: Array<Weekday>, start: string, end: string, }
\ No newline at end of file
@@ -1,7 +1,56 @@
+export * from './bindings/AdaptiveInterval';
+export * from './bindings/BodyArea';
 export * from './bindings/ClientCommand';
 export * from './bindings/ClientQuery';
 export * from './bindings/ClientState';
+export * from './bindings/ConfigFormat';
+export * from './bindings/CoreError';
 export * from './bindings/CoreEvent';
 export * from './bindings/CoreResponse';
+export * from './bindings/DeliveryStatus';
+export * from './bindings/EventFilter';
+export * from './bindings/Exercise';
+export * from './bindings/ExerciseSelection';
+export * from './bindings/ExerciseSet';
+export * from './bindings/HistoryEntry';
+export * from './bindings/HistoryKind';
+export * from './bindings/HookOutput';
+export * from './bindings/HookState';
+export * from './bindings/HttpApiState';
+export * from './bindings/HydrationSettings';
+export * from './bindings/ImportConflict';
+export * from './bindings/ImportMode';
+export * from './bindings/ImportReport';
+export * from './bindings/IntakeDay';
+export * from './bindings/IntegrationSettings';
+export * from './bindings/IpcMessage';
+export * from './bindings/IpcRequest';
+export * from './bindings/Library';
+export * from './bindings/LogLevel';
+export * from './bindings/LogRecord';
+export * from './bindings/MessageTemplate';
+export * from './bindings/NewReminderDefaults';
+export * from './bindings/NotificationAction';
+export * from './bindings/NotificationSettings';
+export * from './bindings/NotificationStyle';
+export * from './bindings/OnceTarget';
+export * from './bindings/OneShot';
+export * from './bindings/Preset';
+export * from './bindings/ProfilePatch';
+export * from './bindings/ProfileRule';
+export * from './bindings/ProfileState';
+export * from './bindings/ReminderKind';
+export * from './bindings/ReminderMessage';
+export * from './bindings/ReminderPatch';
+export * from './bindings/ReminderSchedule';
 export * from './bindings/ReminderState';
+export * from './bindings/ReminderTiming';
+export * from './bindings/Rotation';
+export * from './bindings/Settings';
+export * from './bindings/StartupSettings';
 export * from './bindings/View';
+export * from './bindings/WebhookDelivery';
+export * from './bindings/WebhookPayload';
+export * from './bindings/WebhookState';
+export * from './bindings/Weekday';
+export * from './bindings/WorkingHours';
@@ -0,0 +1,158 @@
+use chrono::{DateTime, Local, Timelike};
+
+use crate::{duration::format_duration, history::{HistoryEntry, HistoryKind}, state::ReminderState};
+
+// how many of the latest responses around the same time of day are looked at
+const WINDOW: usize = 4;
+const MIN_RESPONSES: usize = 3;
+// responses given within this many minutes of the current time of day count
+const TIME_TOLERANCE_MIN: i64 = 60;
+// a mostly snoozed reminder waits a quarter longer, an always answered one a tenth shorter
+const BACK_OFF: f64 = 1.25;
+const TIGHTEN: f64 = 0.9;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Response {
+    Acknowledged,
+    Snoozed,
+    Skipped
+}
+
+impl Response {
+    fn from_kind(kind: &HistoryKind) -> Option<Response> {
+        match kind {
+            HistoryKind::Completed => Some(Response::Acknowledged),
+            HistoryKind::Delayed { .. } => Some(Response::Snoozed),
+            HistoryKind::Skipped => Some(Response::Skipped),
+            _ => None
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Adjustment {
+    pub wait_ms: i32,
+    // shown to the user, e.g. in the ReminderIntervalAdapted event
+    pub reason: String
+}
+
+impl ReminderState {
+    // the interval the reminder is scheduled with, kept within its bounds
+    pub fn effective_wait_ms(&self) -> i32 {
+        match &self.adaptive {
+            Some(a) => a.current_wait_ms.unwrap_or(self.wait_ms).max(a.min_wait_ms).min(a.max_wait_ms),
+            None => self.wait_ms
+        }
+    }
+}
+
+fn minutes_apart(a: DateTime<Local>, b: DateTime<Local>) -> i64 {
+    let minute = |t: DateTime<Local>| i64::from(t.hour() * 60 + t.minute());
+    let diff = (minute(a) - minute(b)).abs();
+    diff.min(24 * 60 - diff)
+}
+
+// the reminder's latest responses given around the same time of day as now, oldest first
+pub fn recent_responses(entries: &[HistoryEntry], reminder_id: &str, now: DateTime<Local>) -> Vec<Response> {
+    let mut responses: Vec<Response> = entries
+        .iter()
+        .rev()
+        .filter(|e| e.reminder_id == reminder_id)
+        .filter(|e| minutes_apart(e.timestamp.with_timezone(&Local), now) <= TIME_TOLERANCE_MIN)
+        .filter_map(|e| Response::from_kind(&e.kind))
+        .take(WINDOW)
+        .collect();
+    responses.reverse();
+    responses
+}
+
+// none while the reminder is not adaptive, too little is known or nothing would change
+pub fn adjust(reminder: &ReminderState, responses: &[Response], now: DateTime<Local>) -> Option<Adjustment> {
+    let bounds = reminder.adaptive.as_ref()?;
+    if responses.len() < MIN_RESPONSES {
+        return None
+    }
+    let current = reminder.effective_wait_ms();
+    let declined = responses.iter().filter(|r| **r != Response::Acknowledged).count();
+    let (wait_ms, why) = if declined * 4 >= responses.len() * 3 {
+        let longer = (f64::from(current) * BACK_OFF) as i32;
+        (longer.min(bounds.max_wait_ms), format!("snoozed or skipped {} of the last {} times", declined, responses.len()))
+    } else if declined == 0 {
+        let shorter = (f64::from(current) * TIGHTEN) as i32;
+        (shorter.max(bounds.min_wait_ms), format!("answered the last {} times", responses.len()))
+    } else {
+        return None
+    };
+    if wait_ms == current {
+        return None
+    }
+    Some(Adjustment {
+        wait_ms,
+        reason: format!(
+            "{} {} around {}, now every {} instead of {}",
+            reminder.name,
+            why,
+            now.format("%H:%M"),
+            format_duration(i64::from(wait_ms)),
+            format_duration(i64::from(current))
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, Local, NaiveDate, TimeZone, Utc};
+    use crate::{history::{HistoryEntry, HistoryKind}, state::{AdaptiveInterval, ReminderState}};
+    use super::{adjust, recent_responses, Response};
+
+    fn adaptive_reminder() -> ReminderState {
+        let mut reminder = ReminderState::new("Stretch".to_string(), 40 * 60 * 1000, 30 * 1000);
+        reminder.adaptive = Some(AdaptiveInterval { min_wait_ms: 30 * 60 * 1000, max_wait_ms: 60 * 60 * 1000, current_wait_ms: None });
+        reminder
+    }
+
+    #[test]
+    fn responses_around_the_same_time_count() {
+        let now = Local.from_local_datetime(&NaiveDate::from_ymd_opt(2024, 1, 3).unwrap().and_hms_opt(14, 0, 0).unwrap()).unwrap();
+        let entry = |hours_ago: i64, kind: HistoryKind| HistoryEntry {
+            timestamp: (now - Duration::hours(hours_ago)).with_timezone(&Utc),
+            reminder_id: "stretch".to_string(),
+            kind
+        };
+        let entries = vec![
+            entry(48, HistoryKind::Completed),
+            entry(24, HistoryKind::Delayed { delay_ms: 1000 }),
+            // a different time of day and not a response
+            entry(3, HistoryKind::Completed),
+            entry(0, HistoryKind::ReminderStarted),
+            entry(0, HistoryKind::Skipped)
+        ];
+        assert_eq!(recent_responses(&entries, "stretch", now), [Response::Acknowledged, Response::Snoozed, Response::Skipped]);
+        assert!(recent_responses(&entries, "other", now).is_empty());
+    }
+
+    #[test]
+    fn intervals_move_within_bounds() {
+        let now = Local::now();
+        let mut reminder = adaptive_reminder();
+        let snoozed = [Response::Snoozed, Response::Snoozed, Response::Acknowledged, Response::Skipped];
+        let answered = [Response::Acknowledged; 3];
+
+        let longer = adjust(&reminder, &snoozed, now).unwrap();
+        assert_eq!(longer.wait_ms, 50 * 60 * 1000);
+        assert!(longer.reason.contains("snoozed or skipped 3 of the last 4 times"));
+
+        // never past the bounds, and no change once they are reached
+        reminder.adaptive.as_mut().unwrap().current_wait_ms = Some(55 * 60 * 1000);
+        assert_eq!(adjust(&reminder, &snoozed, now).unwrap().wait_ms, 60 * 60 * 1000);
+        reminder.adaptive.as_mut().unwrap().current_wait_ms = Some(60 * 60 * 1000);
+        assert!(adjust(&reminder, &snoozed, now).is_none());
+
+        assert_eq!(adjust(&reminder, &answered, now).unwrap().wait_ms, 54 * 60 * 1000);
+        // mixed or too few responses leave the interval alone
+        assert!(adjust(&reminder, &[Response::Acknowledged, Response::Snoozed, Response::Acknowledged], now).is_none());
+        assert!(adjust(&reminder, &answered[..2], now).is_none());
+        reminder.adaptive = None;
+        assert!(adjust(&reminder, &answered, now).is_none());
+    }
+}
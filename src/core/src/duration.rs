@@ -0,0 +1,117 @@
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+// parse durations such as "45m", "1h30m", "90s" or "500ms" into milliseconds,
+// a bare number is taken as milliseconds
+pub fn parse_duration(input: &str) -> Result<i32, String> {
+    let input = input.trim();
+    if let Ok(ms) = input.parse::<i32>() {
+        return Ok(ms)
+    }
+
+    let mut total: i64 = 0;
+    let mut digits = String::new();
+    let mut chars = input.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c.is_ascii_digit() {
+            digits.push(c);
+            continue;
+        }
+        let unit = match c {
+            'h' => 60 * 60 * 1000,
+            'm' if chars.peek() == Some(&'s') => {
+                chars.next();
+                1
+            },
+            'm' => 60 * 1000,
+            's' => 1000,
+            _ => return Err(format!("invalid duration '{}', expected something like 1h30m", input))
+        };
+        let value: i64 = digits.parse().map_err(|_| format!("invalid duration '{}', expected something like 1h30m", input))?;
+        total += value * unit;
+        digits.clear();
+    }
+    if !digits.is_empty() || input.is_empty() {
+        return Err(format!("invalid duration '{}', expected something like 1h30m", input))
+    }
+    i32::try_from(total).map_err(|_| format!("duration '{}' is too long", input))
+}
+
+// the reverse of parse_duration, e.g. 5400000 becomes "1h30m"
+pub fn format_duration(ms: i64) -> String {
+    if ms < 1000 {
+        return format!("{}ms", ms)
+    }
+    let secs = ms / 1000;
+    let (h, m, s) = (secs / 3600, (secs % 3600) / 60, secs % 60);
+    let mut out = String::new();
+    if h > 0 {
+        out.push_str(&format!("{}h", h));
+    }
+    if m > 0 {
+        out.push_str(&format!("{}m", m));
+    }
+    if s > 0 || out.is_empty() {
+        out.push_str(&format!("{}s", s));
+    }
+    // whole seconds only, anything below a second would get lost
+    if ms % 1000 != 0 {
+        out.push_str(&format!("{}ms", ms % 1000));
+    }
+    out
+}
+
+// milliseconds written as a human readable duration in config files,
+// a plain number of milliseconds is accepted as well
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HumanDuration(pub i32);
+
+impl Serialize for HumanDuration {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format_duration(i64::from(self.0)))
+    }
+}
+
+impl<'de> Deserialize<'de> for HumanDuration {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Raw {
+            Ms(i32),
+            Text(String)
+        }
+        match Raw::deserialize(deserializer)? {
+            Raw::Ms(ms) => Ok(HumanDuration(ms)),
+            Raw::Text(t) => parse_duration(&t).map(HumanDuration).map_err(serde::de::Error::custom)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_duration, format_duration, HumanDuration};
+
+    #[test]
+    fn duration_round_trip() {
+        assert_eq!(parse_duration("1h30m"), Ok(90 * 60 * 1000));
+        assert_eq!(parse_duration("45m"), Ok(45 * 60 * 1000));
+        assert_eq!(parse_duration("20s"), Ok(20 * 1000));
+        assert_eq!(parse_duration("500ms"), Ok(500));
+        assert_eq!(parse_duration("1500"), Ok(1500));
+        assert!(parse_duration("soon").is_err());
+        assert!(parse_duration("10").is_ok());
+        assert!(parse_duration("1h30").is_err());
+
+        assert_eq!(format_duration(90 * 60 * 1000), "1h30m");
+        assert_eq!(format_duration(20 * 1000), "20s");
+        assert_eq!(format_duration(500), "500ms");
+        assert_eq!(parse_duration(&format_duration(1500)), Ok(1500));
+    }
+
+    #[test]
+    fn human_duration_serde() {
+        assert_eq!(serde_json::to_string(&HumanDuration(45 * 60 * 1000)).unwrap(), "\"45m\"");
+        assert_eq!(serde_json::from_str::<HumanDuration>("\"1h30m\"").unwrap(), HumanDuration(90 * 60 * 1000));
+        assert_eq!(serde_json::from_str::<HumanDuration>("1500").unwrap(), HumanDuration(1500));
+        assert!(serde_json::from_str::<HumanDuration>("\"soon\"").is_err());
+    }
+}
@@ -0,0 +1,159 @@
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast::{self, error::RecvError};
+use tracing::warn;
+use ts_rs::TS;
+
+use crate::CoreEvent;
+
+// events buffered per subscriber, a subscriber which falls further behind
+// misses the oldest ones and is told how many it missed
+pub const EVENT_CAPACITY: usize = 256;
+
+// which events a subscriber wants, an empty list matches everything
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, TS)]
+#[ts(export)]
+pub struct EventFilter {
+  // event names such as ReminderNewStatus
+  #[serde(default)]
+  pub events: Vec<String>,
+  // events about other reminders are skipped, events which are not about a
+  // single reminder always match
+  #[serde(default)]
+  pub reminder_ids: Vec<String>
+}
+
+impl EventFilter {
+    pub fn matches(&self, event: &CoreEvent) -> bool {
+        // a subscriber always hears about its own missed events and the core stopping
+        if matches!(event, CoreEvent::Lagged { .. } | CoreEvent::ShuttingDown) {
+            return true
+        }
+        if !self.events.is_empty() && !self.events.iter().any(|e| e == event.name()) {
+            return false
+        }
+        match event.reminder_id() {
+            Some(id) if !self.reminder_ids.is_empty() => self.reminder_ids.iter().any(|r| r == id),
+            _ => true
+        }
+    }
+}
+
+// fans core events out to any number of subscribers, publishing never waits on them
+#[derive(Clone)]
+pub struct EventBus {
+    // taken once the core stops so every subscription ends
+    sender: Arc<Mutex<Option<broadcast::Sender<CoreEvent>>>>
+}
+
+impl EventBus {
+
+    pub fn new() -> EventBus {
+        EventBus {
+            sender: Arc::new(Mutex::new(Some(broadcast::channel(EVENT_CAPACITY).0)))
+        }
+    }
+
+    pub fn publish(&self, event: CoreEvent) {
+        if let Some(sender) = self.sender.lock().unwrap_or_else(|e| e.into_inner()).as_ref() {
+            // no subscribers is fine, nobody is listening
+            sender.send(event).unwrap_or(0);
+        }
+    }
+
+    pub fn subscribe(&self, filter: EventFilter) -> Subscription {
+        let receiver = self.sender
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .as_ref()
+            .map(|s| s.subscribe());
+        Subscription { receiver, filter }
+    }
+
+    pub fn close(&self) {
+        self.sender.lock().unwrap_or_else(|e| e.into_inner()).take();
+    }
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        EventBus::new()
+    }
+}
+
+pub struct Subscription {
+    receiver: Option<broadcast::Receiver<CoreEvent>>,
+    filter: EventFilter
+}
+
+impl Subscription {
+    // the next matching event, none once the core has stopped
+    pub async fn recv(&mut self) -> Option<CoreEvent> {
+        let receiver = self.receiver.as_mut()?;
+        loop {
+            match receiver.recv().await {
+                Ok(event) if self.filter.matches(&event) => return Some(event),
+                Ok(_) => continue,
+                Err(RecvError::Lagged(n)) => {
+                    warn!("subscriber skipped {} events", n);
+                    return Some(CoreEvent::Lagged { skipped: u32::try_from(n).unwrap_or(u32::MAX) })
+                },
+                Err(RecvError::Closed) => {
+                    self.receiver = None;
+                    return None
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{messages::ReminderMessage, CoreEvent};
+    use super::{EventBus, EventFilter, EVENT_CAPACITY};
+
+    #[tokio::test]
+    async fn bus_filters_per_subscriber() {
+        let bus = EventBus::new();
+        let mut all = bus.subscribe(EventFilter::default());
+        let mut ends = bus.subscribe(EventFilter { events: vec!["ReminderEnd".to_string()], ..EventFilter::default() });
+        let mut second = bus.subscribe(EventFilter { reminder_ids: vec!["b".to_string()], ..EventFilter::default() });
+
+        bus.publish(CoreEvent::ReminderNewStatus { id: "a".to_string(), next_duration_ms: 10, message: ReminderMessage::default(), exercises: Vec::new() });
+        bus.publish(CoreEvent::ReminderEnd { id: "a".to_string() });
+        bus.publish(CoreEvent::StateChanged { reminder_id: None });
+        bus.publish(CoreEvent::ReminderEnd { id: "b".to_string() });
+        bus.close();
+
+        assert_eq!(all.recv().await, Some(CoreEvent::ReminderNewStatus { id: "a".to_string(), next_duration_ms: 10, message: ReminderMessage::default(), exercises: Vec::new() }));
+        assert_eq!(all.recv().await, Some(CoreEvent::ReminderEnd { id: "a".to_string() }));
+        assert_eq!(all.recv().await, Some(CoreEvent::StateChanged { reminder_id: None }));
+        assert_eq!(all.recv().await, Some(CoreEvent::ReminderEnd { id: "b".to_string() }));
+        assert_eq!(all.recv().await, None);
+
+        assert_eq!(ends.recv().await, Some(CoreEvent::ReminderEnd { id: "a".to_string() }));
+        assert_eq!(ends.recv().await, Some(CoreEvent::ReminderEnd { id: "b".to_string() }));
+        assert_eq!(ends.recv().await, None);
+
+        assert_eq!(second.recv().await, Some(CoreEvent::StateChanged { reminder_id: None }));
+        assert_eq!(second.recv().await, Some(CoreEvent::ReminderEnd { id: "b".to_string() }));
+        assert_eq!(second.recv().await, None);
+
+        // subscribing after the core stopped ends straight away
+        assert_eq!(bus.subscribe(EventFilter::default()).recv().await, None);
+    }
+
+    #[tokio::test]
+    async fn bus_never_blocks_on_slow_subscriber() {
+        let bus = EventBus::new();
+        let mut slow = bus.subscribe(EventFilter::default());
+
+        // publishing past the capacity returns straight away
+        for _ in 0..EVENT_CAPACITY + 10 {
+            bus.publish(CoreEvent::StateChanged { reminder_id: None });
+        }
+
+        assert_eq!(slow.recv().await, Some(CoreEvent::Lagged { skipped: 10 }));
+        assert_eq!(slow.recv().await, Some(CoreEvent::StateChanged { reminder_id: None }));
+    }
+}
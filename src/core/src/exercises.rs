@@ -0,0 +1,184 @@
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use tracing::error;
+use ts_rs::TS;
+
+use crate::{random::SharedRng, state::ReminderState};
+
+// how many exercises per reminder count as recently shown, those are only
+// picked again when nothing else fits
+const RECENT_LIMIT: usize = 8;
+
+static LIBRARY: Lazy<Library> = Lazy::new(|| {
+    serde_json::from_str(include_str!("../data/exercises.json")).unwrap_or_else(|e| {
+        error!("the bundled exercise library is invalid: {}", e);
+        Library::default()
+    })
+});
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export)]
+pub enum BodyArea {
+  Neck,
+  Shoulders,
+  Back,
+  Wrists,
+  Legs,
+  Eyes,
+  FullBody
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
+#[ts(export)]
+pub struct Exercise {
+  pub id: String,
+  pub title: String,
+  pub area: BodyArea,
+  // roughly how long the steps take
+  pub duration_ms: i32,
+  pub steps: Vec<String>
+}
+
+// a named selection of exercises, e.g. the ones which can be done seated
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
+#[ts(export)]
+pub struct ExerciseSet {
+  pub id: String,
+  pub name: String,
+  pub exercises: Vec<String>
+}
+
+// which exercises a reminder shows during its break
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
+#[serde(tag = "key", content = "value")]
+#[ts(export)]
+pub enum ExerciseSelection {
+  Set(String),
+  Area(BodyArea)
+}
+
+// the exercises shipped with the core, see data/exercises.json
+#[derive(Serialize, Deserialize, Debug, Clone, Default, TS)]
+#[ts(export)]
+pub struct Library {
+  pub sets: Vec<ExerciseSet>,
+  pub exercises: Vec<Exercise>
+}
+
+impl Library {
+
+    pub fn bundled() -> &'static Library {
+        &LIBRARY
+    }
+
+    // false for a set which does not exist, e.g. after it was renamed
+    pub fn contains(&self, selection: &ExerciseSelection) -> bool {
+        match selection {
+            ExerciseSelection::Set(id) => self.sets.iter().any(|s| s.id == *id),
+            ExerciseSelection::Area(_) => true
+        }
+    }
+
+    pub fn candidates(&self, selection: &ExerciseSelection) -> Vec<&Exercise> {
+        match selection {
+            ExerciseSelection::Set(id) => self.sets
+                .iter()
+                .filter(|s| s.id == *id)
+                .flat_map(|s| s.exercises.iter())
+                .filter_map(|e| self.exercises.iter().find(|x| x.id == *e))
+                .collect(),
+            ExerciseSelection::Area(area) => self.exercises.iter().filter(|e| e.area == *area).collect()
+        }
+    }
+}
+
+// picks each occurrence's exercises, remembering what every reminder showed recently
+pub struct ExercisePicker {
+    // reminder id -> exercise ids, oldest first
+    recent: HashMap<String, VecDeque<String>>,
+    rng: SharedRng
+}
+
+impl ExercisePicker {
+
+    pub fn new(rng: SharedRng) -> ExercisePicker {
+        ExercisePicker { recent: HashMap::new(), rng }
+    }
+
+    // as many exercises as fit the break, exercises not shown recently first
+    pub fn pick(&mut self, library: &Library, reminder: &ReminderState) -> Vec<Exercise> {
+        let selection = match &reminder.exercises {
+            Some(s) => s,
+            None => return Vec::new()
+        };
+        let recent = self.recent.entry(reminder.id.clone()).or_default();
+        let (mut fresh, mut seen): (Vec<&Exercise>, Vec<&Exercise>) = library
+            .candidates(selection)
+            .into_iter()
+            .partition(|e| !recent.contains(&e.id));
+        self.rng.shuffle(&mut fresh);
+        seen.sort_by_key(|e| recent.iter().position(|id| *id == e.id));
+
+        let mut budget = reminder.duration_ms;
+        let mut picked = Vec::new();
+        for exercise in fresh.into_iter().chain(seen) {
+            if exercise.duration_ms <= budget && !picked.iter().any(|p: &Exercise| p.id == exercise.id) {
+                budget -= exercise.duration_ms;
+                picked.push(exercise.clone());
+            }
+        }
+
+        for exercise in &picked {
+            recent.retain(|id| *id != exercise.id);
+            recent.push_back(exercise.id.clone());
+        }
+        while recent.len() > RECENT_LIMIT {
+            recent.pop_front();
+        }
+        picked
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use crate::{random::SharedRng, state::ReminderState};
+    use super::{BodyArea, ExercisePicker, ExerciseSelection, Library};
+
+    #[test]
+    fn bundled_library_is_consistent() {
+        let library = Library::bundled();
+        assert!(!library.exercises.is_empty());
+        let ids: HashSet<&str> = library.exercises.iter().map(|e| e.id.as_str()).collect();
+        assert_eq!(ids.len(), library.exercises.len());
+        for set in &library.sets {
+            assert!(set.exercises.iter().all(|e| ids.contains(e.as_str())), "set {} names a missing exercise", set.id);
+        }
+        assert!(library.exercises.iter().all(|e| e.duration_ms > 0 && !e.steps.is_empty()));
+    }
+
+    #[test]
+    fn picks_fit_the_break_without_repeats() {
+        let library = Library::bundled();
+        let mut picker = ExercisePicker::new(SharedRng::seeded(7));
+        let mut reminder = ReminderState::new("Eyes".to_string(), 60 * 1000, 45 * 1000);
+
+        assert!(picker.pick(library, &reminder).is_empty());
+
+        reminder.exercises = Some(ExerciseSelection::Area(BodyArea::Eyes));
+        let first = picker.pick(library, &reminder);
+        assert!(!first.is_empty());
+        assert!(first.iter().map(|e| e.duration_ms).sum::<i32>() <= reminder.duration_ms);
+        assert!(first.iter().all(|e| e.area == BodyArea::Eyes));
+
+        // the next break starts with exercises which were not just shown
+        let second = picker.pick(library, &reminder);
+        assert!(!first.iter().any(|e| e.id == second[0].id));
+
+        reminder.exercises = Some(ExerciseSelection::Set("missing".to_string()));
+        assert!(picker.pick(library, &reminder).is_empty());
+        assert!(!library.contains(&ExerciseSelection::Set("missing".to_string())));
+    }
+}
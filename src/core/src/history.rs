@@ -0,0 +1,242 @@
+use chrono::{DateTime, Duration, Local, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::fs::{self, OpenOptions};
+use std::io::{BufRead, BufReader, Write, Error};
+use std::sync::{Arc, Mutex, PoisonError};
+use tracing::{error, info};
+use ts_rs::TS;
+
+use crate::hydration::MAX_INTAKE_DAYS;
+
+pub static HISTORY_NAME: &str = "history.jsonl";
+
+// number of entries kept in memory for queries
+const MEMORY_LIMIT: usize = 500;
+// entries older than this are dropped from the file on startup, intake stats never look further back
+const KEEP_DAYS: i64 = MAX_INTAKE_DAYS as i64;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, TS)]
+#[ts(export)]
+pub struct HistoryEntry {
+  pub timestamp: DateTime<Utc>,
+  pub reminder_id: String,
+  pub kind: HistoryKind
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, TS)]
+#[serde(tag = "key", content = "data")]
+#[ts(export)]
+pub enum HistoryKind {
+  ReminderStarted,
+  ReminderEnded,
+  Completed,
+  Skipped,
+  Delayed { delay_ms: i32 },
+  HookRan(HookOutput),
+  // water drunk, logged through a hydration reminder
+  Intake { amount_ml: i32 }
+}
+
+// the captured result of a single command hook
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, TS)]
+#[ts(export)]
+pub struct HookOutput {
+  pub event: String,
+  pub command: String,
+  pub exit_code: Option<i32>,
+  pub stdout: String,
+  pub stderr: String,
+  pub timed_out: bool
+}
+
+// an append-only log of what happened to each reminder
+pub struct History {
+    data_path: String,
+    entries: VecDeque<HistoryEntry>,
+    // local date -> ml, unlike the entries this covers the whole file
+    intake: BTreeMap<NaiveDate, i32>,
+    // recorded but not yet on disk, whichever append runs first writes all of them in order
+    pending: Arc<Mutex<Vec<HistoryEntry>>>,
+    // one append at a time so lines are neither interleaved nor reordered
+    writing: Arc<Mutex<()>>
+}
+
+impl History {
+
+    pub fn new(data_path: &str) -> History {
+      History {
+        data_path: data_path.to_string(),
+        entries: VecDeque::new(),
+        intake: BTreeMap::new(),
+        pending: Arc::new(Mutex::new(Vec::new())),
+        writing: Arc::new(Mutex::new(()))
+      }
+    }
+
+    pub fn read_disk(&mut self) -> Result<(),Error> {
+      let history_path = format!("{}/{}", &self.data_path, HISTORY_NAME);
+      let file = fs::File::open(&history_path)?;
+      let reader = BufReader::new(file);
+      let oldest = Utc::now() - Duration::days(KEEP_DAYS);
+      let mut kept = Vec::new();
+      let mut dropped = 0;
+      for line in reader.lines() {
+        // skip lines which were only partially written
+        match serde_json::from_str::<HistoryEntry>(&line?) {
+          Ok(entry) if entry.timestamp >= oldest => kept.push(entry),
+          _ => dropped += 1
+        }
+      }
+
+      // rewrite the file without them so it does not grow forever
+      if dropped > 0 {
+        info!("dropping {} old history entries", dropped);
+        let tmp_path = format!("{}.tmp", history_path);
+        write_entries(&tmp_path, &kept, false)?;
+        fs::rename(&tmp_path, &history_path)?;
+      }
+      for entry in kept {
+        self.push(entry);
+      }
+      Ok(())
+    }
+
+    pub fn record(&mut self, reminder_id: &str, kind: HistoryKind) {
+      let entry = HistoryEntry {
+        timestamp: Utc::now(),
+        reminder_id: reminder_id.to_string(),
+        kind
+      };
+      // only write to disk if data path is set
+      if !self.data_path.is_empty() {
+        self.pending.lock().unwrap_or_else(PoisonError::into_inner).push(entry.clone());
+        let append = self.append();
+        // the core loop must not wait for the disk, outside a runtime there is no loop to block
+        match tokio::runtime::Handle::try_current() {
+          Ok(handle) => { handle.spawn_blocking(append); },
+          Err(_) => append()
+        }
+      }
+      self.push(entry);
+    }
+
+    // waits until everything recorded so far is on disk
+    pub async fn flush(&self) {
+      if let Err(e) = tokio::task::spawn_blocking(self.append()).await {
+        error!("cannot append to history: {:?}", e);
+      }
+    }
+
+    pub fn entries(&self) -> Vec<HistoryEntry> {
+      self.entries.iter().cloned().collect()
+    }
+
+    // how often the reminder started since local midnight, as far as kept in memory
+    pub fn count_today(&self, reminder_id: &str) -> usize {
+      let today = Local::now().date_naive();
+      self.entries
+        .iter()
+        .filter(|e| e.reminder_id == reminder_id && e.kind == HistoryKind::ReminderStarted)
+        .filter(|e| e.timestamp.with_timezone(&Local).date_naive() == today)
+        .count()
+    }
+
+    // the water logged on a local date
+    pub fn intake_on(&self, date: NaiveDate) -> i32 {
+      self.intake.get(&date).copied().unwrap_or(0)
+    }
+
+    fn push(&mut self, entry: HistoryEntry) {
+      if let HistoryKind::Intake { amount_ml } = entry.kind {
+        *self.intake.entry(entry.timestamp.with_timezone(&Local).date_naive()).or_insert(0) += amount_ml;
+      }
+      if self.entries.len() == MEMORY_LIMIT {
+        self.entries.pop_front();
+      }
+      self.entries.push_back(entry);
+    }
+
+    fn append(&self) -> impl FnOnce() + Send + 'static {
+      let history_path = format!("{}/{}", self.data_path, HISTORY_NAME);
+      let (pending, writing) = (self.pending.clone(), self.writing.clone());
+      move || {
+        let _writing = writing.lock().unwrap_or_else(PoisonError::into_inner);
+        let entries = std::mem::take(&mut *pending.lock().unwrap_or_else(PoisonError::into_inner));
+        if entries.is_empty() {
+          return
+        }
+        write_entries(&history_path, &entries, true).unwrap_or_else(|e| error!("cannot append to history: {:?}", e));
+      }
+    }
+}
+
+fn write_entries(path: &str, entries: &[HistoryEntry], append: bool) -> Result<(),Error> {
+    let mut file = OpenOptions::new().create(true).write(true).append(append).truncate(!append).open(path)?;
+    let mut lines = String::new();
+    for entry in entries {
+      lines.push_str(&serde_json::to_string(entry)?);
+      lines.push('\n');
+    }
+    file.write_all(lines.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, Utc};
+    use std::fs;
+    use super::{History, HistoryEntry, HistoryKind, HISTORY_NAME, KEEP_DAYS};
+
+    fn lines(data_dir: &str) -> Vec<HistoryEntry> {
+        fs::read_to_string(format!("{}/{}", data_dir, HISTORY_NAME))
+            .unwrap()
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn records_are_appended_in_order() {
+        let data_dir = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+        fs::create_dir_all(&data_dir).unwrap();
+        let data_dir = data_dir.to_str().unwrap().to_string();
+
+        let mut history = History::new(&data_dir);
+        for delay_ms in 0..50 {
+            history.record("stretch", HistoryKind::Delayed { delay_ms });
+        }
+        history.flush().await;
+
+        let written = lines(&data_dir);
+        fs::remove_dir_all(&data_dir).unwrap_or(());
+        assert_eq!(written, history.entries());
+    }
+
+    #[test]
+    fn old_entries_are_dropped_on_load() {
+        let data_dir = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+        fs::create_dir_all(&data_dir).unwrap();
+        let data_dir = data_dir.to_str().unwrap().to_string();
+
+        let entry = |days_ago: i64| HistoryEntry {
+            timestamp: Utc::now() - Duration::days(days_ago),
+            reminder_id: "water".to_string(),
+            kind: HistoryKind::Intake { amount_ml: 250 }
+        };
+        let recent = vec![entry(KEEP_DAYS - 1), entry(0)];
+        let mut file = String::new();
+        for e in [entry(KEEP_DAYS + 1)].iter().chain(recent.iter()) {
+            file.push_str(&serde_json::to_string(e).unwrap());
+            file.push('\n');
+        }
+        fs::write(format!("{}/{}", data_dir, HISTORY_NAME), file).unwrap();
+
+        let mut history = History::new(&data_dir);
+        history.read_disk().unwrap();
+
+        let written = lines(&data_dir);
+        fs::remove_dir_all(&data_dir).unwrap_or(());
+        assert_eq!(history.entries(), recent);
+        assert_eq!(written, recent);
+    }
+}
@@ -0,0 +1,167 @@
+use std::io;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::process::Command;
+
+use crate::history::HookOutput;
+
+// captured stdout/stderr is cut off after this many bytes so the history stays small
+const OUTPUT_LIMIT: usize = 4 * 1024;
+
+pub static HOOK_EVENT_START: &str = "start";
+pub static HOOK_EVENT_END: &str = "end";
+
+// everything a hook is told about the reminder that triggered it
+#[derive(Debug, Clone)]
+pub struct HookContext {
+    pub event: &'static str,
+    pub reminder_id: String,
+    pub reminder_name: String,
+    pub duration_ms: i32
+}
+
+impl HookContext {
+    fn env(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("PAZ_EVENT", self.event.to_string()),
+            ("PAZ_REMINDER_ID", self.reminder_id.clone()),
+            ("PAZ_REMINDER_NAME", self.reminder_name.clone()),
+            ("PAZ_DURATION_MS", self.duration_ms.to_string()),
+        ]
+    }
+}
+
+// run a hook through the platform shell, killing it once the timeout elapses
+pub async fn run_hook(command: &str, ctx: &HookContext, timeout: Duration) -> HookOutput {
+    let mut cmd = shell(command);
+    cmd.envs(ctx.env())
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+
+    let mut output = HookOutput {
+        event: ctx.event.to_string(),
+        command: command.to_string(),
+        exit_code: None,
+        stdout: String::new(),
+        stderr: String::new(),
+        timed_out: false
+    };
+
+    let child = match cmd.spawn() {
+        Ok(c) => c,
+        Err(e) => {
+            output.stderr = e.to_string();
+            return output
+        }
+    };
+    let pid = child.id();
+
+    match tokio::time::timeout(timeout, child.wait_with_output()).await {
+        Ok(Ok(out)) => {
+            output.exit_code = out.status.code();
+            output.stdout = truncate(&out.stdout);
+            output.stderr = truncate(&out.stderr);
+        },
+        Ok(Err(e)) => output.stderr = e.to_string(),
+        // the child is dropped here which kills it, whatever it started goes with it
+        Err(_) => {
+            kill_group(pid);
+            output.timed_out = true
+        }
+    }
+    output
+}
+
+#[cfg(windows)]
+fn shell(command: &str) -> Command {
+    let mut cmd = Command::new("cmd");
+    cmd.arg("/C").arg(command);
+    cmd
+}
+
+#[cfg(windows)]
+fn kill_group(_pid: Option<u32>) {}
+
+// the hook runs in a process group of its own, see kill_group
+#[cfg(not(windows))]
+fn shell(command: &str) -> Command {
+    let mut cmd = Command::new("sh");
+    cmd.arg("-c").arg(command);
+    // SAFETY: setpgid is async-signal-safe and nothing else runs between fork and exec
+    unsafe {
+        cmd.pre_exec(|| match libc::setpgid(0, 0) {
+            0 => Ok(()),
+            _ => Err(io::Error::last_os_error())
+        });
+    }
+    cmd
+}
+
+#[cfg(not(windows))]
+fn kill_group(pid: Option<u32>) {
+    if let Some(pid) = pid.and_then(|p| i32::try_from(p).ok()) {
+        // SAFETY: only signals the group the hook was started in
+        unsafe { libc::kill(-pid, libc::SIGKILL) };
+    }
+}
+
+fn truncate(bytes: &[u8]) -> String {
+    let end = bytes.len().min(OUTPUT_LIMIT);
+    String::from_utf8_lossy(&bytes[..end]).trim_end().to_string()
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::time::Duration;
+
+    use super::{run_hook, HookContext, HOOK_EVENT_START};
+
+    fn create_test_context() -> HookContext {
+        HookContext {
+            event: HOOK_EVENT_START,
+            reminder_id: "r1".to_string(),
+            reminder_name: "Stretch".to_string(),
+            duration_ms: 15000
+        }
+    }
+
+    #[tokio::test]
+    async fn hook_receives_env_and_captures_output() {
+        let ctx = create_test_context();
+
+        let out = run_hook("echo $PAZ_REMINDER_ID $PAZ_DURATION_MS; echo oops >&2; exit 3", &ctx, Duration::from_secs(5)).await;
+
+        assert_eq!(out.stdout, "r1 15000");
+        assert_eq!(out.stderr, "oops");
+        assert_eq!(out.exit_code, Some(3));
+        assert!(!out.timed_out);
+    }
+
+    #[tokio::test]
+    async fn hook_times_out() {
+        let ctx = create_test_context();
+
+        let out = run_hook("sleep 5", &ctx, Duration::from_millis(100)).await;
+
+        assert!(out.timed_out);
+        assert_eq!(out.exit_code, None);
+    }
+
+    #[tokio::test]
+    async fn hook_timeout_kills_what_it_started() {
+        let ctx = create_test_context();
+        let pid_file = std::env::temp_dir().join(format!("paz-hook-{}", uuid::Uuid::new_v4()));
+
+        let out = run_hook(&format!("sleep 5 & echo $! > {}; wait", pid_file.display()), &ctx, Duration::from_millis(200)).await;
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let pid = std::fs::read_to_string(&pid_file).unwrap();
+        std::fs::remove_file(&pid_file).unwrap_or(());
+        // gone, or a zombie nobody reaped yet
+        let state = std::fs::read_to_string(format!("/proc/{}/stat", pid.trim())).unwrap_or_default();
+
+        assert!(out.timed_out);
+        assert!(state.is_empty() || state.contains(") Z"), "sleep is still running: {}", state);
+    }
+}
@@ -0,0 +1,409 @@
+use axum::{
+    extract::{Extension, Path, Query},
+    http::{Request, StatusCode, header::AUTHORIZATION},
+    middleware::{self, Next},
+    response::{IntoResponse, Response, sse::{Event, KeepAlive, Sse}},
+    routing::{get, patch, post, put},
+    Json, Router,
+};
+use futures_util::stream::{self, Stream};
+use serde::Deserialize;
+use std::{convert::Infallible, net::{SocketAddr, TcpListener}};
+use tracing::{error, info};
+
+use crate::{ClientCommand, ClientQuery, CoreController, CoreError, CoreResponse, events::EventFilter, state::{ReminderPatch, ReminderState}};
+
+#[derive(Clone)]
+struct ApiToken(String);
+
+#[derive(Deserialize)]
+struct DelayBody {
+    delay: i32
+}
+
+#[derive(Deserialize)]
+struct CreateBody {
+    name: String,
+    wait_ms: i32,
+    duration_ms: i32,
+    #[serde(default)]
+    is_active: bool
+}
+
+#[derive(Deserialize)]
+struct ActiveBody {
+    is_active: bool
+}
+
+#[derive(Deserialize)]
+struct OrderBody {
+    ids: Vec<String>
+}
+
+// comma separated lists, e.g. /events?events=ReminderNewStatus,ReminderEnd
+#[derive(Deserialize)]
+struct EventsParams {
+    events: Option<String>,
+    reminders: Option<String>
+}
+
+impl EventsParams {
+    fn into_filter(self) -> EventFilter {
+        let split = |list: Option<String>| list
+            .map(|l| l.split(',').filter(|v| !v.is_empty()).map(|v| v.to_string()).collect())
+            .unwrap_or_default();
+        EventFilter {
+            events: split(self.events),
+            reminder_ids: split(self.reminders)
+        }
+    }
+}
+
+// start the api if it is enabled in the client state, settings are read once on startup
+pub async fn start(controller: CoreController) {
+    let state = match controller.query(ClientQuery::ClientGetState).await {
+        Ok(CoreResponse::ClientGetState(s)) => s,
+        _ => return
+    };
+    if !state.http_api.enabled {
+        return
+    }
+    let addr = SocketAddr::from(([127, 0, 0, 1], state.http_api.port));
+    let listener = match TcpListener::bind(addr) {
+        Ok(l) => l,
+        Err(e) => {
+            error!("cannot listen on {}: {:?}", addr, e);
+            return
+        }
+    };
+    info!("listening on http://{}", addr);
+    if let Err(e) = serve(listener, state.http_api.token, controller).await {
+        error!("{:?}", e);
+    }
+}
+
+pub async fn serve(listener: TcpListener, token: String, controller: CoreController) -> Result<(), hyper::Error> {
+    axum::Server::from_tcp(listener)?
+        .serve(router(token, controller).into_make_service())
+        .await
+}
+
+fn router(token: String, controller: CoreController) -> Router {
+    Router::new()
+        .route("/state", get(get_state))
+        .route("/reminders", get(get_reminders).put(put_reminders).post(create_reminder))
+        .route("/reminders/order", put(reorder_reminders))
+        .route("/reminders/:id", patch(update_reminder).delete(delete_reminder))
+        .route("/reminders/:id/active", put(set_reminder_active))
+        .route("/reminders/:id/delay", post(delay_reminder))
+        .route("/reminders/:id/skip", post(skip_reminder))
+        .route("/reminders/:id/complete", post(complete_reminder))
+        .route("/pause", post(pause))
+        .route("/resume", post(resume))
+        .route("/history", get(get_history))
+        .route("/webhooks/deliveries", get(get_deliveries))
+        .route("/query", post(query))
+        .route("/command", post(command))
+        .route("/events", get(events))
+        .layer(middleware::from_fn(authorize))
+        .layer(Extension(controller))
+        .layer(Extension(ApiToken(token)))
+}
+
+// every request needs the bearer token, only the event stream may pass it as
+// ?token= since browsers cannot set headers on an EventSource
+async fn authorize<B>(req: Request<B>, next: Next<B>) -> Result<Response, StatusCode> {
+    let expected = match req.extensions().get::<ApiToken>() {
+        Some(ApiToken(t)) if !t.is_empty() => t.clone(),
+        _ => return Err(StatusCode::UNAUTHORIZED)
+    };
+    let header = req.headers()
+        .get(AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .map(|v| v.to_string());
+    // a token in the url ends up in logs and history, so only where there is no other way
+    let query = Some(req.uri())
+        .filter(|uri| uri.path() == "/events")
+        .and_then(|uri| uri.query())
+        .and_then(|q| q.split('&').find_map(|p| p.strip_prefix("token=")))
+        .map(|v| v.to_string());
+
+    match header.or(query) {
+        Some(t) if same_token(&t, &expected) => Ok(next.run(req).await),
+        _ => Err(StatusCode::UNAUTHORIZED)
+    }
+}
+
+// compare every byte so the time taken does not tell how much of the token matched
+fn same_token(given: &str, expected: &str) -> bool {
+    given.len() == expected.len() && given.bytes()
+        .zip(expected.bytes())
+        .fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+fn respond(res: Result<CoreResponse, CoreError>) -> Response {
+    match res {
+        Ok(r) => Json(r).into_response(),
+        Err(e) => {
+            let status = match e {
+                CoreError::Validation { .. } => StatusCode::UNPROCESSABLE_ENTITY,
+                CoreError::NotFound { .. } => StatusCode::NOT_FOUND,
+                CoreError::InvalidRequest { .. } => StatusCode::BAD_REQUEST,
+                CoreError::Integration { .. } => StatusCode::BAD_GATEWAY,
+                CoreError::Unavailable => StatusCode::SERVICE_UNAVAILABLE,
+                CoreError::Storage { .. } | CoreError::Scheduler { .. } | CoreError::Internal { .. } => StatusCode::INTERNAL_SERVER_ERROR
+            };
+            (status, Json(e)).into_response()
+        }
+    }
+}
+
+async fn get_state(Extension(core): Extension<CoreController>) -> Response {
+    respond(core.query(ClientQuery::ClientGetState).await)
+}
+
+async fn get_reminders(Extension(core): Extension<CoreController>) -> Response {
+    respond(core.query(ClientQuery::ReminderGetSchedule).await)
+}
+
+async fn put_reminders(Extension(core): Extension<CoreController>, Json(reminders): Json<Vec<ReminderState>>) -> Response {
+    if let Err(e) = check_hooks(&core, &reminders).await {
+        return respond(Err(e))
+    }
+    respond(core.command(ClientCommand::SaveReminders { reminders }).await)
+}
+
+// hook commands run in a shell, like an import the api cannot change them
+async fn check_hooks(core: &CoreController, reminders: &[ReminderState]) -> Result<(), CoreError> {
+    let current = match core.query(ClientQuery::ClientGetState).await? {
+        CoreResponse::ClientGetState(s) => s.reminders,
+        other => return Err(CoreError::Internal { reason: format!("unexpected response {:?}", other) })
+    };
+    for (i, reminder) in reminders.iter().enumerate() {
+        let hooks = current.iter().find(|r| r.id == reminder.id).map(|r| r.hooks.clone()).unwrap_or_default();
+        if reminder.hooks != hooks {
+            return Err(CoreError::Validation { field: format!("reminders[{}].hooks", i), reason: "hook commands can only be changed in the app".to_string() })
+        }
+    }
+    Ok(())
+}
+
+async fn create_reminder(Extension(core): Extension<CoreController>, Json(body): Json<CreateBody>) -> Response {
+    respond(core.command(ClientCommand::CreateReminder {
+        name: body.name,
+        wait_ms: body.wait_ms,
+        duration_ms: body.duration_ms,
+        is_active: body.is_active
+    }).await)
+}
+
+async fn update_reminder(Extension(core): Extension<CoreController>, Path(id): Path<String>, Json(patch): Json<ReminderPatch>) -> Response {
+    respond(core.command(ClientCommand::UpdateReminder { id, patch: Box::new(patch) }).await)
+}
+
+async fn delete_reminder(Extension(core): Extension<CoreController>, Path(id): Path<String>) -> Response {
+    respond(core.command(ClientCommand::DeleteReminder { id }).await)
+}
+
+async fn set_reminder_active(Extension(core): Extension<CoreController>, Path(id): Path<String>, Json(body): Json<ActiveBody>) -> Response {
+    respond(core.command(ClientCommand::SetReminderActive { id, is_active: body.is_active }).await)
+}
+
+async fn reorder_reminders(Extension(core): Extension<CoreController>, Json(body): Json<OrderBody>) -> Response {
+    respond(core.command(ClientCommand::ReorderReminders { ids: body.ids }).await)
+}
+
+async fn delay_reminder(Extension(core): Extension<CoreController>, Path(id): Path<String>, Json(body): Json<DelayBody>) -> Response {
+    respond(core.command(ClientCommand::DelayReminder { id, delay: body.delay }).await)
+}
+
+async fn skip_reminder(Extension(core): Extension<CoreController>, Path(id): Path<String>) -> Response {
+    respond(core.command(ClientCommand::SkipReminder { id }).await)
+}
+
+async fn complete_reminder(Extension(core): Extension<CoreController>, Path(id): Path<String>) -> Response {
+    respond(core.command(ClientCommand::CompleteReminder { id }).await)
+}
+
+async fn pause(Extension(core): Extension<CoreController>) -> Response {
+    respond(core.command(ClientCommand::SetPaused { paused: true }).await)
+}
+
+async fn resume(Extension(core): Extension<CoreController>) -> Response {
+    respond(core.command(ClientCommand::SetPaused { paused: false }).await)
+}
+
+async fn get_history(Extension(core): Extension<CoreController>) -> Response {
+    respond(core.query(ClientQuery::HistoryGet).await)
+}
+
+async fn get_deliveries(Extension(core): Extension<CoreController>) -> Response {
+    respond(core.query(ClientQuery::WebhookGetDeliveries).await)
+}
+
+async fn query(Extension(core): Extension<CoreController>, Json(query): Json<ClientQuery>) -> Response {
+    respond(core.query(query).await)
+}
+
+async fn command(Extension(core): Extension<CoreController>, Json(cmd): Json<ClientCommand>) -> Response {
+    if let ClientCommand::SaveReminders { reminders } = &cmd {
+        if let Err(e) = check_hooks(&core, reminders).await {
+            return respond(Err(e))
+        }
+    }
+    respond(core.command(cmd).await)
+}
+
+// stream core events as server-sent events named after the event
+async fn events(Extension(core): Extension<CoreController>, Query(params): Query<EventsParams>) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let subscription = core.subscribe(params.into_filter());
+    let stream = stream::unfold(subscription, |mut subscription| async move {
+        let e = subscription.recv().await?;
+        let event = Event::default()
+            .event(e.name())
+            .json_data(&e)
+            .unwrap_or_default();
+        Some((Ok(event), subscription))
+    });
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::TcpListener;
+
+    use crate::{Core, CoreError, CoreResponse};
+    use super::{same_token, serve};
+
+    // run a core in a temporary data directory with the api on a random port
+    fn start_test_api(token: &str) -> (std::path::PathBuf, String) {
+        let data_dir = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+        let mut core = Core::new(data_dir.clone());
+        let controller = core.get_controller();
+        tokio::spawn(async move { core.start().await });
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(serve(listener, token.to_string(), controller));
+        (data_dir, url)
+    }
+
+    #[tokio::test]
+    async fn http_requires_bearer_token() {
+        let (data_dir, url) = start_test_api("secret");
+        let client = reqwest::Client::new();
+
+        let missing = client.get(format!("{}/state", url)).send().await.unwrap();
+        let wrong = client.get(format!("{}/state", url)).bearer_auth("nope").send().await.unwrap();
+        let ok = client.get(format!("{}/state", url)).bearer_auth("secret").send().await.unwrap();
+        std::fs::remove_dir_all(&data_dir).unwrap_or(());
+
+        assert_eq!(missing.status(), 401);
+        assert_eq!(wrong.status(), 401);
+        assert_eq!(ok.status(), 200);
+        assert!(matches!(ok.json::<CoreResponse>().await.unwrap(), CoreResponse::ClientGetState(_)));
+    }
+
+    #[tokio::test]
+    async fn http_query_token_only_on_events() {
+        let (data_dir, url) = start_test_api("secret");
+        let client = reqwest::Client::new();
+
+        let state = client.get(format!("{}/state?token=secret", url)).send().await.unwrap();
+        let events = client.get(format!("{}/events?token=secret", url)).send().await.unwrap();
+        let wrong = client.get(format!("{}/events?token=secreT", url)).send().await.unwrap();
+        std::fs::remove_dir_all(&data_dir).unwrap_or(());
+
+        assert_eq!(state.status(), 401);
+        assert_eq!(events.status(), 200);
+        assert_eq!(wrong.status(), 401);
+    }
+
+    #[test]
+    fn tokens_compare_whole() {
+        assert!(same_token("secret", "secret"));
+        assert!(!same_token("secreT", "secret"));
+        assert!(!same_token("secret", "secret2"));
+        assert!(!same_token("", "secret"));
+    }
+
+    #[tokio::test]
+    async fn http_patch_delay_and_events() {
+        let (data_dir, url) = start_test_api("secret");
+        let client = reqwest::Client::new();
+
+        let state = match client.get(format!("{}/state", url)).bearer_auth("secret").send().await.unwrap().json::<CoreResponse>().await.unwrap() {
+            CoreResponse::ClientGetState(s) => s,
+            other => panic!("unexpected response {:?}", other)
+        };
+        let id = state.reminders[0].id.clone();
+
+        // subscribe before the reminder is activated so its first event is seen
+        let mut events = client.get(format!("{}/events?token=secret", url)).send().await.unwrap();
+        let patched = client.patch(format!("{}/reminders/{}", url, id))
+            .bearer_auth("secret")
+            .json(&serde_json::json!({"is_active": true, "wait_ms": 60 * 1000}))
+            .send()
+            .await
+            .unwrap();
+        let delayed = client.post(format!("{}/reminders/{}/delay", url, id))
+            .bearer_auth("secret")
+            .json(&serde_json::json!({"delay": 50}))
+            .send()
+            .await
+            .unwrap();
+        let missing = client.delete(format!("{}/reminders/unknown", url)).bearer_auth("secret").send().await.unwrap();
+
+        let mut text = String::new();
+        while !text.contains("event:ReminderNewStatus") {
+            let chunk = tokio::time::timeout(std::time::Duration::from_secs(5), events.chunk()).await.unwrap().unwrap().unwrap();
+            text.push_str(&String::from_utf8_lossy(&chunk));
+        }
+        std::fs::remove_dir_all(&data_dir).unwrap_or(());
+
+        assert_eq!(patched.status(), 200);
+        assert_eq!(delayed.status(), 200);
+        assert_eq!(missing.status(), 404);
+        assert_eq!(missing.json::<CoreError>().await.unwrap().code(), "not_found");
+        assert!(text.starts_with("event:StateChanged"), "got {}", text);
+        assert!(text.contains(&id), "got {}", text);
+    }
+
+    #[tokio::test]
+    async fn http_cannot_change_hooks() {
+        let (data_dir, url) = start_test_api("secret");
+        let client = reqwest::Client::new();
+        let get_state = || async {
+            match client.get(format!("{}/state", url)).bearer_auth("secret").send().await.unwrap().json::<CoreResponse>().await.unwrap() {
+                CoreResponse::ClientGetState(s) => s,
+                other => panic!("unexpected response {:?}", other)
+            }
+        };
+        let mut reminders = get_state().await.reminders;
+        let id = reminders[0].id.clone();
+
+        let patched = client.patch(format!("{}/reminders/{}", url, id))
+            .bearer_auth("secret")
+            .json(&serde_json::json!({"name": "Renamed", "hooks": {"on_start": "touch /tmp/pwned", "on_end": null}}))
+            .send()
+            .await
+            .unwrap();
+        reminders[0].hooks.on_start = Some("touch /tmp/pwned".to_string());
+        let put = client.put(format!("{}/reminders", url)).bearer_auth("secret").json(&reminders).send().await.unwrap();
+        let command = client.post(format!("{}/command", url))
+            .bearer_auth("secret")
+            .json(&serde_json::json!({"key": "SaveReminders", "params": {"reminders": reminders}}))
+            .send()
+            .await
+            .unwrap();
+        let after = get_state().await;
+        std::fs::remove_dir_all(&data_dir).unwrap_or(());
+
+        assert_eq!(patched.status(), 200);
+        assert_eq!(put.status(), 422);
+        assert_eq!(command.status(), 422);
+        assert_eq!(after.reminders[0].name, "Renamed");
+        assert_eq!(after.reminders[0].hooks.on_start, None);
+    }
+}
\ No newline at end of file
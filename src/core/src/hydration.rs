@@ -0,0 +1,131 @@
+use chrono::{Duration, NaiveDate, NaiveDateTime, NaiveTime};
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+use crate::{history::History, profiles::parse_time, state::WorkingHours};
+
+pub const DEFAULT_INTAKE_DAYS: u32 = 7;
+pub const MAX_INTAKE_DAYS: u32 = 366;
+// a reminder far behind the goal comes at most twice as often
+const MIN_PACE: f64 = 0.5;
+// the part of the day the goal is spread over while working hours are off
+const DAY_START_HOUR: u32 = 8;
+const DAY_END_HOUR: u32 = 22;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
+#[ts(export)]
+pub struct IntakeDay {
+  // local date as YYYY-MM-DD
+  pub date: String,
+  pub amount_ml: i32,
+  pub goal_ml: i32
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Pace {
+    OnTrack,
+    // the interval to wait before the next reminder
+    Behind(i32),
+    GoalMet
+}
+
+// the part of the day the goal is spread over
+fn day_window(hours: &WorkingHours) -> Option<(NaiveTime, NaiveTime)> {
+    let day = (NaiveTime::from_hms_opt(DAY_START_HOUR, 0, 0), NaiveTime::from_hms_opt(DAY_END_HOUR, 0, 0));
+    let window = match (parse_time(&hours.start), parse_time(&hours.end)) {
+        (Some(start), Some(end)) if hours.enabled && start < end => (Some(start), Some(end)),
+        _ => day
+    };
+    match window {
+        (Some(start), Some(end)) => Some((start, end)),
+        _ => None
+    }
+}
+
+// the share of the goal which should have been drunk by now
+pub fn expected_by(goal_ml: i32, now: NaiveDateTime, hours: &WorkingHours) -> i32 {
+    let (start, end) = match day_window(hours) {
+        Some(window) => window,
+        None => return 0
+    };
+    let elapsed = (now.time() - start).num_seconds().max(0) as f64;
+    let length = (end - start).num_seconds() as f64;
+    (f64::from(goal_ml) * (elapsed / length).min(1.0)) as i32
+}
+
+pub fn pace(goal_ml: i32, logged_ml: i32, interval_ms: i32, now: NaiveDateTime, hours: &WorkingHours) -> Pace {
+    if logged_ml >= goal_ml {
+        return Pace::GoalMet
+    }
+    let expected = expected_by(goal_ml, now, hours);
+    if logged_ml >= expected {
+        return Pace::OnTrack
+    }
+    let factor = (f64::from(logged_ml) / f64::from(expected)).max(MIN_PACE);
+    Pace::Behind((f64::from(interval_ms) * factor) as i32)
+}
+
+// where a reminder which met today's goal picks up again
+pub fn next_day_start(now: NaiveDateTime, hours: &WorkingHours) -> NaiveDateTime {
+    let start = day_window(hours).map_or(now.time(), |(start, _)| start);
+    (now.date() + Duration::days(1)).and_time(start)
+}
+
+// the intake of the last days, oldest first and today last
+pub fn intake_days(history: &History, today: NaiveDate, days: u32, goal_ml: i32) -> Vec<IntakeDay> {
+    (0..days)
+        .rev()
+        .filter_map(|back| today.checked_sub_signed(chrono::Duration::days(i64::from(back))))
+        .map(|date| IntakeDay {
+            date: date.format("%Y-%m-%d").to_string(),
+            amount_ml: history.intake_on(date),
+            goal_ml
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Local, NaiveDate};
+    use crate::{history::{History, HistoryKind}, profiles::parse_time, state::WorkingHours};
+    use super::{intake_days, next_day_start, pace, Pace};
+
+    #[test]
+    fn pace_follows_the_goal() {
+        let at = |time: &str| NaiveDate::from_ymd_opt(2024, 1, 1).unwrap().and_time(parse_time(time).unwrap());
+        let off = WorkingHours::default();
+        let hour = 60 * 60 * 1000;
+
+        // halfway through the 08:00 to 22:00 day
+        assert_eq!(pace(2000, 1000, hour, at("15:00"), &off), Pace::OnTrack);
+        assert_eq!(pace(2000, 750, hour, at("15:00"), &off), Pace::Behind(hour * 3 / 4));
+        assert_eq!(pace(2000, 0, hour, at("15:00"), &off), Pace::Behind(hour / 2));
+        assert_eq!(pace(2000, 0, hour, at("07:00"), &off), Pace::OnTrack);
+        assert_eq!(pace(2000, 2000, hour, at("09:00"), &off), Pace::GoalMet);
+
+        // with working hours the goal is spread over them instead
+        let office = WorkingHours { enabled: true, ..WorkingHours::default() };
+        assert_eq!(pace(2000, 1000, hour, at("13:00"), &office), Pace::OnTrack);
+        assert_eq!(pace(2000, 1000, hour, at("17:00"), &office), Pace::Behind(hour / 2));
+
+        // a met goal waits for the next day
+        let tomorrow = NaiveDate::from_ymd_opt(2024, 1, 2).unwrap();
+        assert_eq!(next_day_start(at("15:00"), &off), tomorrow.and_time(parse_time("08:00").unwrap()));
+        assert_eq!(next_day_start(at("15:00"), &office), tomorrow.and_time(parse_time(&office.start).unwrap()));
+    }
+
+    #[test]
+    fn intake_is_summed_per_day() {
+        let mut history = History::new("");
+        history.record("water", HistoryKind::Intake { amount_ml: 250 });
+        history.record("water", HistoryKind::Completed);
+        history.record("water", HistoryKind::Intake { amount_ml: 300 });
+
+        let today = Local::now().date_naive();
+        let days = intake_days(&history, today, 3, 2000);
+        assert_eq!(days.len(), 3);
+        assert_eq!(days[2].date, today.format("%Y-%m-%d").to_string());
+        assert_eq!(days[2].amount_ml, 550);
+        assert_eq!(days[0].amount_ml, 0);
+    }
+}
@@ -0,0 +1,243 @@
+use serde::{Deserialize, Serialize};
+use std::{fs, io, os::unix::fs::{DirBuilderExt, PermissionsExt}, path::{Path, PathBuf}};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::{UnixListener, UnixStream, unix::OwnedWriteHalf}
+};
+use tracing::{info, warn};
+use ts_rs::TS;
+
+use crate::{ClientCommand, ClientQuery, CoreController, CoreError, CoreEvent, CoreResponse, DATA_DIR_NAME, events::{EventFilter, Subscription}};
+
+pub static SOCKET_NAME: &str = "paz.sock";
+
+// a single line sent by a client
+#[derive(Serialize, Deserialize, Debug, TS)]
+#[serde(tag = "type", content = "data")]
+#[ts(export)]
+pub enum IpcRequest {
+    Query(ClientQuery),
+    Command(ClientCommand),
+    // without a filter every event is sent
+    Subscribe(Option<EventFilter>)
+}
+
+// a single line sent back to a client, every request gets exactly one
+// response or error, events follow once subscribed
+#[derive(Serialize, Deserialize, Debug, TS)]
+#[serde(tag = "type", content = "data")]
+#[ts(export)]
+pub enum IpcMessage {
+    Response(CoreResponse),
+    Error(CoreError),
+    Event(CoreEvent)
+}
+
+// the socket lives in the same directory as the client state
+pub fn socket_path(data_dir: &Path) -> PathBuf {
+    data_dir.join(DATA_DIR_NAME).join(SOCKET_NAME)
+}
+
+// accept clients on the socket until the listener fails
+pub async fn serve(path: PathBuf, controller: CoreController) -> io::Result<()> {
+    let listener = bind(&path).await?;
+    info!("listening on {}", path.display());
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let controller = controller.clone();
+        tokio::spawn(async move {
+            if let Err(e) = handle_client(stream, controller).await {
+                warn!("client disconnected: {:?}", e);
+            }
+        });
+    }
+}
+
+async fn bind(path: &Path) -> io::Result<UnixListener> {
+    if path.exists() {
+        // a socket which still accepts connections belongs to another running instance
+        if UnixStream::connect(path).await.is_ok() {
+            return Err(io::Error::new(io::ErrorKind::AddrInUse, "paz is already listening on this socket"));
+        }
+        fs::remove_file(path)?;
+    }
+    // only the current user may control the core, the socket is bound inside a
+    // directory nobody else can enter and moved into place once locked down
+    let private = path.with_file_name(format!(".{}", uuid::Uuid::new_v4()));
+    fs::DirBuilder::new().mode(0o700).create(&private)?;
+    let bound = private.join(SOCKET_NAME);
+    let res = UnixListener::bind(&bound).and_then(|listener| {
+        fs::set_permissions(&bound, fs::Permissions::from_mode(0o600))?;
+        fs::rename(&bound, path)?;
+        Ok(listener)
+    });
+    fs::remove_file(&bound).unwrap_or(());
+    fs::remove_dir(&private).unwrap_or(());
+    res
+}
+
+async fn handle_client(stream: UnixStream, controller: CoreController) -> io::Result<()> {
+    let (read, mut write) = stream.into_split();
+    let mut lines = BufReader::new(read).lines();
+    let mut events: Option<Subscription> = None;
+
+    loop {
+        tokio::select! {
+            line = lines.next_line() => {
+                let line = match line? {
+                    Some(l) => l,
+                    None => return Ok(())
+                };
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let msg = match serde_json::from_str::<IpcRequest>(&line) {
+                    Ok(IpcRequest::Query(q)) => into_message(controller.query(q).await),
+                    Ok(IpcRequest::Command(c)) => into_message(controller.command(c).await),
+                    Ok(IpcRequest::Subscribe(filter)) => {
+                        events = Some(controller.subscribe(filter.unwrap_or_default()));
+                        IpcMessage::Response(CoreResponse::Success(()))
+                    },
+                    Err(e) => IpcMessage::Error(CoreError::InvalidRequest { reason: e.to_string() })
+                };
+                write_message(&mut write, &msg).await?;
+            },
+            event = next_event(&mut events) => match event {
+                Some(e) => write_message(&mut write, &IpcMessage::Event(e)).await?,
+                // the core has stopped
+                None => return Ok(())
+            }
+        }
+    }
+}
+
+// wait for the next event, or forever when the client has not subscribed
+async fn next_event(events: &mut Option<Subscription>) -> Option<CoreEvent> {
+    match events {
+        Some(rx) => rx.recv().await,
+        None => std::future::pending().await
+    }
+}
+
+fn into_message(res: Result<CoreResponse, CoreError>) -> IpcMessage {
+    match res {
+        Ok(r) => IpcMessage::Response(r),
+        Err(e) => IpcMessage::Error(e)
+    }
+}
+
+async fn write_message(write: &mut OwnedWriteHalf, msg: &IpcMessage) -> io::Result<()> {
+    let mut json = serde_json::to_string(msg)?;
+    json.push('\n');
+    write.write_all(json.as_bytes()).await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{os::unix::fs::PermissionsExt, path::PathBuf};
+    use tokio::{io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines}, net::{UnixStream, unix::{OwnedReadHalf, OwnedWriteHalf}}};
+
+    use crate::{Core, CoreError, CoreEvent, CoreResponse, state::ReminderState};
+    use super::{serve, socket_path, IpcMessage};
+
+    // run a core in a temporary data directory and connect to its socket
+    async fn connect_test_client() -> (PathBuf, Lines<BufReader<OwnedReadHalf>>, OwnedWriteHalf) {
+        let data_dir = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+        let mut core = Core::new(data_dir.clone());
+        let controller = core.get_controller();
+        tokio::spawn(async move { core.start().await });
+        let path = socket_path(&data_dir);
+        tokio::spawn(serve(path.clone(), controller));
+
+        for _ in 0..50 {
+            if let Ok(s) = UnixStream::connect(&path).await {
+                let (read, write) = s.into_split();
+                return (data_dir, BufReader::new(read).lines(), write)
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        panic!("socket never came up")
+    }
+
+    async fn read_message(lines: &mut Lines<BufReader<OwnedReadHalf>>) -> IpcMessage {
+        serde_json::from_str(&lines.next_line().await.unwrap().unwrap()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn ipc_query_command_and_subscribe() {
+        let (data_dir, mut lines, mut write) = connect_test_client().await;
+
+        // query
+        write.write_all(b"{\"type\":\"Query\",\"data\":{\"key\":\"ClientGetState\"}}\n").await.unwrap();
+        let msg = read_message(&mut lines).await;
+        assert!(matches!(msg, IpcMessage::Response(CoreResponse::ClientGetState(_))));
+
+        // subscribe, then save a reminder which fires straight away
+        write.write_all(b"{\"type\":\"Subscribe\"}\n").await.unwrap();
+        let msg = read_message(&mut lines).await;
+        assert!(matches!(msg, IpcMessage::Response(CoreResponse::Success(()))));
+
+        let mut reminder = ReminderState::new("r1".to_string(), 1000, 50);
+        reminder.is_active = true;
+        let save = serde_json::json!({"type": "Command", "data": {"key": "SaveReminders", "params": {"reminders": [reminder]}}});
+        write.write_all(format!("{}\n", save).as_bytes()).await.unwrap();
+        let msg = read_message(&mut lines).await;
+        assert!(matches!(msg, IpcMessage::Response(CoreResponse::Success(()))));
+
+        let msg = read_message(&mut lines).await;
+        assert!(matches!(msg, IpcMessage::Event(CoreEvent::StateChanged { reminder_id: None })));
+
+        let msg = read_message(&mut lines).await;
+        std::fs::remove_dir_all(&data_dir).unwrap_or(());
+        match msg {
+            IpcMessage::Event(CoreEvent::ReminderNewStatus { id, .. }) => assert_eq!(id, reminder.id),
+            other => panic!("expected a reminder event but got {:?}", other)
+        }
+    }
+
+    #[tokio::test]
+    async fn ipc_socket_is_private() {
+        let (data_dir, _lines, _write) = connect_test_client().await;
+        let path = socket_path(&data_dir);
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        // nothing is left behind from binding
+        let entries = std::fs::read_dir(path.parent().unwrap()).unwrap()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_name().to_string_lossy().starts_with('.'))
+            .count();
+        std::fs::remove_dir_all(&data_dir).unwrap_or(());
+        assert_eq!(mode & 0o777, 0o600);
+        assert_eq!(entries, 0);
+    }
+
+    #[tokio::test]
+    async fn ipc_subscribe_with_filter() {
+        let (data_dir, mut lines, mut write) = connect_test_client().await;
+
+        write.write_all(b"{\"type\":\"Subscribe\",\"data\":{\"events\":[\"ReminderNewStatus\"]}}\n").await.unwrap();
+        let msg = read_message(&mut lines).await;
+        assert!(matches!(msg, IpcMessage::Response(CoreResponse::Success(()))));
+
+        let mut reminder = ReminderState::new("r1".to_string(), 1000, 50);
+        reminder.is_active = true;
+        let save = serde_json::json!({"type": "Command", "data": {"key": "SaveReminders", "params": {"reminders": [reminder]}}});
+        write.write_all(format!("{}\n", save).as_bytes()).await.unwrap();
+        let msg = read_message(&mut lines).await;
+        assert!(matches!(msg, IpcMessage::Response(CoreResponse::Success(()))));
+
+        // StateChanged is filtered out
+        let msg = read_message(&mut lines).await;
+        std::fs::remove_dir_all(&data_dir).unwrap_or(());
+        assert!(matches!(msg, IpcMessage::Event(CoreEvent::ReminderNewStatus { .. })), "got {:?}", msg);
+    }
+
+    #[tokio::test]
+    async fn ipc_invalid_request_is_an_error() {
+        let (data_dir, mut lines, mut write) = connect_test_client().await;
+
+        write.write_all(b"not json\n").await.unwrap();
+        let msg = read_message(&mut lines).await;
+        std::fs::remove_dir_all(&data_dir).unwrap_or(());
+        assert!(matches!(msg, IpcMessage::Error(CoreError::InvalidRequest { .. })));
+    }
+}
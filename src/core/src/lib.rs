@@ -1,14 +1,57 @@
-use cache::Cache;
-use chrono::{DateTime, Utc};
+use cache::{Cache, ReminderSchedule};
+use events::{EventBus, EventFilter, Subscription};
+use exercises::{Exercise, ExercisePicker, Library};
+use chrono::{DateTime, Local, TimeZone, Utc};
+use config::{ConfigFormat, ImportMode, ImportReport};
+use history::{History, HistoryKind, HistoryEntry, HookOutput};
+use hydration::{IntakeDay, Pace};
+use hooks::{HookContext, HOOK_EVENT_START, HOOK_EVENT_END};
+use logging::{Logger, LogRecord};
+use messages::{Messages, ReminderMessage};
+use notification::{Notifier, NotificationAction};
+use once::OnceTarget;
+use presets::Preset;
+use random::SharedRng;
 use serde::{Deserialize, Serialize};
-use state::{ClientState, ReminderState};
-use tokio::sync::{oneshot, mpsc::{UnboundedSender, UnboundedReceiver, unbounded_channel, Sender, Receiver, channel}};
+use state::{ClientState, LogLevel, NotificationStyle, OneShot, ProfilePatch, ProfileState, ReminderKind, ReminderPatch, ReminderState, Settings};
+use tokio::sync::{oneshot, mpsc::{UnboundedSender, UnboundedReceiver, unbounded_channel}};
 use ts_rs::TS;
-use std::{path::PathBuf, fs};
+use futures_util::FutureExt;
+use std::{any::Any, collections::HashSet, fs, panic::AssertUnwindSafe, path::PathBuf};
 use thiserror::Error;
+use tracing::{debug, error, info, warn, Instrument};
+use watcher::{StateDiff, StateWatcher};
+use webhook::{Webhooks, WebhookPayload, WebhookDelivery};
 
 pub mod state;
+pub mod adaptive;
 pub mod cache;
+pub mod config;
+pub mod duration;
+pub mod events;
+pub mod exercises;
+pub mod webhook;
+pub mod history;
+pub mod hooks;
+pub mod hydration;
+pub mod logging;
+pub mod messages;
+pub mod notification;
+pub mod once;
+pub mod presets;
+pub mod profiles;
+pub mod random;
+pub mod validation;
+pub mod watcher;
+#[cfg(unix)]
+pub mod ipc;
+#[cfg(feature = "http-api")]
+pub mod http;
+
+// the directory inside the platform's data directory which holds everything paz stores
+pub static DATA_DIR_NAME: &str = "paz";
+// log records returned by GetRecentLogs when no limit is given
+const DEFAULT_LOG_LIMIT: usize = 200;
 
 
 pub fn add_one(x: i32) -> i32 {
@@ -24,6 +67,12 @@ pub struct Core {
     
     state: ClientState,
     cache: Cache,
+    webhooks: Webhooks,
+    history: History,
+    notifier: Notifier,
+    messages: Messages,
+    exercises: ExercisePicker,
+    rng: SharedRng,
 
     // a channel for the ui to send queries w/ returnable values
     query_channel: (
@@ -43,15 +92,30 @@ pub struct Core {
         UnboundedReceiver<InternalEvent>
     ),
 
-    // a channel sender for core to send events to the ui
-    event_sender: Sender<CoreEvent>
+    // every listener such as the ui, ipc clients and the http api subscribes here
+    events: EventBus,
+
+    logger: Logger,
+
+    // reloads the client state when it is edited outside of the app
+    watcher: Option<StateWatcher>,
+
+    // reminders which started but have not ended yet
+    in_progress: HashSet<String>,
+
+    // the profile whose rules applied at the last check, rules only switch
+    // profiles when this changes so a manual switch sticks until then
+    rule_profile: Option<String>,
+
+    // set once shut down, start returns after the current message
+    stopped: bool
 }
 
 impl Core {
 
     // create new instance of core, run startup tasks
-    pub fn new(mut data_dir: PathBuf) -> (Core, Receiver<CoreEvent>) {
-        data_dir = data_dir.join("paz");
+    pub fn new(mut data_dir: PathBuf) -> Core {
+        data_dir = data_dir.join(DATA_DIR_NAME);
         let data_dir = data_dir.to_str().unwrap();
 
         // create data directory if it doesn't exist
@@ -62,87 +126,286 @@ impl Core {
 
         // load from disk
         state.read_disk().unwrap_or_default();
-        state.save();
+        let logger = Logger::init(data_dir, state.log_level);
+        validation::deactivate_invalid(&mut state.reminders);
+        if state.settings.startup.start_paused {
+            state.paused = true;
+        }
+        state.save().unwrap_or_else(|e| error!("cannot save client state: {:?}", e));
+
+        // load pending webhook deliveries
+        let webhooks = Webhooks::new(data_dir);
+
+        // load reminder history
+        let mut history = History::new(data_dir);
+        history.read_disk().unwrap_or_default();
 
         // build channels
         let internal_channel = unbounded_channel::<InternalEvent>();
-        let event_channel = channel(100);
 
         // build cache
+        let rng = SharedRng::from_entropy();
         let cache = Cache::new(&state, CoreContext { 
-            intenal_sender: internal_channel.0.clone() 
+            intenal_sender: internal_channel.0.clone(),
+            rng: rng.clone()
         });
 
-        (Core {
+        Core {
             state,
             cache,
+            webhooks,
+            history,
+            notifier: Notifier::new(),
+            messages: Messages::new(rng.clone()),
+            exercises: ExercisePicker::new(rng.clone()),
+            rng,
             query_channel: unbounded_channel(),
             command_channel: unbounded_channel(),
             internal_channel,
-            event_sender: event_channel.0 
-        }, event_channel.1)
+            events: EventBus::new(),
+            logger,
+            watcher: None,
+            in_progress: HashSet::new(),
+            rule_profile: None,
+            stopped: false
+        }
     }
 
     pub fn initialize(&mut self) {
-        println!("Info: Core: initializing...");
+        info!("initializing...");
 
         // setup reminder cache instance
         self.cache = Cache::new(&self.state, self.get_context());
-        self.cache.start()
+        self.cache.start();
+
+        // resume delivering queued webhooks
+        self.webhooks.start();
+
+        // connect to the desktop's notification service
+        self.notifier.start(self.get_context());
+
+        // switch profiles by time of day and weekday
+        profiles::start_rule_timer(self.get_context());
+
+        self.watch_state()
+    }
+
+    // reload the client state file whenever it changes on disk
+    pub fn watch_state(&mut self) {
+        match watcher::watch(&self.state.data_path, self.get_context()) {
+            Ok(w) => self.watcher = Some(w),
+            Err(e) => warn!("cannot watch the client state, edits need a restart: {:?}", e)
+        }
+    }
+
+    // e.g. for the app to decide whether to open its window on launch
+    pub fn settings(&self) -> &Settings {
+        &self.state.settings
     }
 
     pub fn get_controller(&self) -> CoreController {
         CoreController {
           query_tx: self.query_channel.0.clone(),
           command_tx: self.command_channel.0.clone(),
+          events: self.events.clone(),
         }
     }
 
     pub fn get_context(&self) -> CoreContext {
         CoreContext { 
-            intenal_sender:  self.internal_channel.0.clone()
+            intenal_sender:  self.internal_channel.0.clone(),
+            rng: self.rng.clone()
         }
     }
 
+    // a panic while handling a single message is reported instead of taking the core down
     pub async fn start(&mut self) {
-        loop {
+        while !self.stopped {
             tokio::select! {
                 Some(q) = self.query_channel.1.recv() => {
-                    let res = self.exec_query(q.data).await;
+                    let span = tracing::info_span!("query", key = q.data.name());
+                    let res = match AssertUnwindSafe(self.exec_query(q.data)).catch_unwind().instrument(span).await {
+                        Ok(res) => res,
+                        Err(p) => Err(self.engine_error(None, panic_message(p)))
+                    };
                     q.tx_return.send(res).unwrap_or(());
                 }
                 Some(c) = self.command_channel.1.recv() => {
-                    let res = self.exec_command(c.data).await;
+                    let span = tracing::info_span!("command", key = c.data.name());
+                    let res = match AssertUnwindSafe(self.exec_command(c.data)).catch_unwind().instrument(span).await {
+                        Ok(res) => res,
+                        Err(p) => Err(self.engine_error(None, panic_message(p)))
+                    };
                     c.tx_return.send(res).unwrap_or(());
                 }
-                Some(e) = self.internal_channel.1.recv() => self.exec_event(e).await
+                Some(e) = self.internal_channel.1.recv() => {
+                    // one span per reminder occurrence, from its start to its end
+                    let span = match e.reminder_id() {
+                        Some(id) => tracing::info_span!("reminder", id),
+                        None => tracing::info_span!("event")
+                    };
+                    if let Err(p) = AssertUnwindSafe(self.exec_event(e)).catch_unwind().instrument(span).await {
+                        self.engine_error(None, panic_message(p));
+                    }
+                }
             }
         }
+        // every subscription ends once the core has stopped
+        self.events.close();
+        info!("stopped");
     }
 
     // handle queries
     pub async fn exec_query(&self, query: ClientQuery) -> Result<CoreResponse, CoreError> {
-        println!("Info: Query: {:?}", query);
+        debug!("{:?}", query);
         Ok(match query {
-            ClientQuery::ClientGetState => CoreResponse::ClientGetState(self.state.clone()),
-            _ => todo!()
+            ClientQuery::ClientGetState => CoreResponse::ClientGetState(Box::new(self.state.clone())),
+            ClientQuery::WebhookGetDeliveries => CoreResponse::WebhookGetDeliveries(self.webhooks.deliveries()),
+            ClientQuery::HistoryGet => CoreResponse::HistoryGet(self.history.entries()),
+            ClientQuery::ReminderGetSchedule => CoreResponse::ReminderGetSchedule(self.cache.schedule(&self.state.reminders)),
+            ClientQuery::GetSettings => CoreResponse::Settings(self.state.settings.clone()),
+            ClientQuery::ExerciseGetLibrary => CoreResponse::ExerciseLibrary(Library::bundled().clone()),
+            ClientQuery::PresetGetCatalogue => CoreResponse::Presets(presets::catalogue()),
+            ClientQuery::IntakeGetStats { days } => {
+                let days = days.unwrap_or(hydration::DEFAULT_INTAKE_DAYS).min(hydration::MAX_INTAKE_DAYS);
+                let goal = self.state.settings.hydration.daily_goal_ml;
+                CoreResponse::IntakeStats(hydration::intake_days(&self.history, Local::now().date_naive(), days, goal))
+            },
+            ClientQuery::GetRecentLogs { limit } => CoreResponse::Logs(self.logger.recent(limit.unwrap_or(DEFAULT_LOG_LIMIT))),
+            ClientQuery::JobGetRunning => return Err(CoreError::InvalidRequest { reason: "JobGetRunning is not supported".to_string() })
         })
     }
 
     // handle commands
     pub async fn exec_command(&mut self, command: ClientCommand) -> Result<CoreResponse, CoreError> {
-        println!("Info: Command: {:?}", command);
+        debug!("{:?}", command);
         Ok(match command {
             ClientCommand::AddOne { value } => CoreResponse::Sum(value + 1),
             ClientCommand::Add { x, y } => {
                 CoreResponse::Sum(x + y)
             },
             ClientCommand::SaveReminders { reminders } => {
-                let res = self.save_reminders(reminders);
+                validation::validate_reminders(&reminders)?;
+                let res = self.save_reminders(reminders)?;
+                self.emit(CoreEvent::StateChanged { reminder_id: None });
                 res
             },
+            ClientCommand::CreateReminder { name, wait_ms, duration_ms, is_active } => {
+                let reminder = self.create_reminder(name, wait_ms, duration_ms, is_active)?;
+                self.emit(CoreEvent::StateChanged { reminder_id: Some(reminder.id.clone()) });
+                CoreResponse::Reminder(reminder)
+            },
+            ClientCommand::ScheduleOnce { name, target, duration_ms, delete_when_done } => {
+                let reminder = self.schedule_once(name, &target, duration_ms, delete_when_done)?;
+                self.emit(CoreEvent::StateChanged { reminder_id: Some(reminder.id.clone()) });
+                CoreResponse::Reminder(reminder)
+            },
+            ClientCommand::UpdateReminder { id, patch } => {
+                let reminder = self.update_reminder(&id, *patch)?;
+                self.emit(CoreEvent::StateChanged { reminder_id: Some(id) });
+                CoreResponse::Reminder(reminder)
+            },
+            ClientCommand::SetReminderActive { id, is_active } => {
+                let patch = ReminderPatch { is_active: Some(is_active), ..ReminderPatch::default() };
+                let reminder = self.update_reminder(&id, patch)?;
+                self.emit(CoreEvent::StateChanged { reminder_id: Some(id) });
+                CoreResponse::Reminder(reminder)
+            },
+            ClientCommand::DeleteReminder { id } => {
+                let reminder = self.delete_reminder(&id)?;
+                self.emit(CoreEvent::StateChanged { reminder_id: Some(id) });
+                CoreResponse::Reminder(reminder)
+            },
+            ClientCommand::ReorderReminders { ids } => {
+                let reminders = self.reorder_reminders(&ids)?;
+                self.emit(CoreEvent::StateChanged { reminder_id: None });
+                CoreResponse::Reminders(reminders)
+            },
+            ClientCommand::UpdateSettings { settings } => {
+                validation::validate_settings(&settings)?;
+                self.state.settings = settings;
+                self.state.save()?;
+                self.emit(CoreEvent::StateChanged { reminder_id: None });
+                CoreResponse::Settings(self.state.settings.clone())
+            },
+            ClientCommand::CreateProfile { name } => {
+                let profile = self.create_profile(name)?;
+                self.emit(CoreEvent::StateChanged { reminder_id: None });
+                CoreResponse::Profile(profile)
+            },
+            ClientCommand::UpdateProfile { id, patch } => {
+                let profile = self.update_profile(&id, patch)?;
+                self.emit(CoreEvent::StateChanged { reminder_id: None });
+                CoreResponse::Profile(profile)
+            },
+            ClientCommand::DeleteProfile { id } => {
+                let profile = self.delete_profile(&id)?;
+                self.emit(CoreEvent::StateChanged { reminder_id: None });
+                CoreResponse::Profile(profile)
+            },
+            ClientCommand::SwitchProfile { id } => {
+                self.switch_profile(&id)?;
+                CoreResponse::Success(())
+            },
+            ClientCommand::ApplyPreset { id } => {
+                let reminder = self.apply_preset(&id)?;
+                self.emit(CoreEvent::StateChanged { reminder_id: Some(reminder.id.clone()) });
+                CoreResponse::Reminder(reminder)
+            },
+            ClientCommand::FinishOnboarding { presets } => {
+                let reminders = self.finish_onboarding(presets)?;
+                self.emit(CoreEvent::StateChanged { reminder_id: None });
+                CoreResponse::Reminders(reminders)
+            },
+            ClientCommand::ReloadState => self.reload_state()?,
+            ClientCommand::ExportConfig { format } => CoreResponse::Config(config::export(&self.state, format)?),
+            ClientCommand::ImportConfig { content, mode } => CoreResponse::ConfigImported(self.import_config(&content, mode)?),
+            ClientCommand::Shutdown => self.shutdown().await?,
             ClientCommand::DelayReminder { id, delay } => {
-                self.cache.delay_reminder(&id, delay);
+                self.reminder(&id)?;
+                validation::validate_delay(delay)?;
+                if !self.snooze_once(&id, delay)? {
+                    self.cache.delay_reminder(&id, delay);
+                }
+                self.history.record(&id, HistoryKind::Delayed { delay_ms: delay });
+                self.notifier.close(&id);
+                self.adapt_interval(&id);
+                CoreResponse::Success(())
+            },
+            ClientCommand::SkipReminder { id } => {
+                self.reminder(&id)?;
+                // skipping a running break ends it now
+                if self.cache.cancel_end(&id) {
+                    self.end_reminder(id.clone()).await;
+                }
+                self.cache.skip_reminder(&id);
+                self.history.record(&id, HistoryKind::Skipped);
+                self.notifier.close(&id);
+                self.adapt_interval(&id);
+                CoreResponse::Success(())
+            },
+            ClientCommand::SetPaused { paused } => {
+                self.state.paused = paused;
+                self.cache.set_paused(paused);
+                self.state.save()?;
+                CoreResponse::Success(())
+            },
+            ClientCommand::CompleteReminder { id } => {
+                self.reminder(&id)?;
+                self.history.record(&id, HistoryKind::Completed);
+                // completing a hydration reminder counts as drinking its amount
+                if let Some(ReminderKind::Hydration { amount_ml }) = self.find_reminder(&id).map(|r| r.kind.clone()) {
+                    self.history.record(&id, HistoryKind::Intake { amount_ml });
+                }
+                self.notifier.close(&id);
+                self.adapt_interval(&id);
+                CoreResponse::Success(())
+            },
+            ClientCommand::LogIntake { id, amount_ml } => CoreResponse::IntakeStats(vec![self.log_intake(&id, amount_ml)?]),
+            ClientCommand::SetLogLevel { level } => {
+                self.state.log_level = level;
+                self.logger.set_level(level);
+                self.state.save()?;
                 CoreResponse::Success(())
             }
             // _ => todo!()
@@ -151,30 +414,611 @@ impl Core {
 
     // handle events
     pub async fn exec_event(&mut self, event: InternalEvent) {
-        println!("Info: Event {:?}", event);
+        debug!("{:?}", event);
         match event {
-            InternalEvent::ReminderStart { id, next_duration_ms } => self.start_reminder(id, next_duration_ms).await
+            InternalEvent::ReminderStart { id, next_duration_ms } => self.start_reminder(id, next_duration_ms).await,
+            InternalEvent::ReminderEnd { id } => self.end_reminder(id).await,
+            InternalEvent::HookFinished { reminder_id, output } => {
+                self.history.record(&reminder_id, HistoryKind::HookRan(output))
+            },
+            InternalEvent::NotificationAction { reminder_id, action } => {
+                if let Err(e) = self.exec_command(action.command(reminder_id)).await {
+                    error!("notification action failed: {}", e);
+                }
+            },
+            InternalEvent::ReminderTaskFailed { id, reason } => {
+                let error = CoreError::Scheduler { reason };
+                error!("{}", error);
+                self.emit(CoreEvent::EngineError { reminder_id: Some(id), error })
+            },
+            InternalEvent::ProfileRulesDue => self.apply_profile_rules(),
+            InternalEvent::StateFileChanged => {
+                if let Err(error) = self.reload_state() {
+                    warn!("ignoring the edited client state: {}", error);
+                    self.emit(CoreEvent::EngineError { reminder_id: None, error })
+                }
+            }
         }
     }
 
-    fn save_reminders(&mut self, reminders: Vec<ReminderState>) -> CoreResponse {
-        
-        // persist
+    // report a panic caught while handling a message
+    fn engine_error(&self, reminder_id: Option<String>, reason: String) -> CoreError {
+        let error = CoreError::Internal { reason };
+        error!("{}", error);
+        self.emit(CoreEvent::EngineError { reminder_id, error: error.clone() });
+        error
+    }
+
+    // changes are applied even when they cannot be written to disk, the caller
+    // still learns about the storage error
+    fn save_reminders(&mut self, reminders: Vec<ReminderState>) -> Result<CoreResponse, CoreError> {
         self.state.reminders = reminders;
-        self.state.save();
 
         // resync cache
         self.cache.resync(&self.state.reminders);
-        CoreResponse::Success(())
+
+        // persist
+        self.state.save()?;
+        Ok(CoreResponse::Success(()))
+    }
+
+    fn create_reminder(&mut self, name: String, wait_ms: i32, duration_ms: i32, is_active: bool) -> Result<ReminderState, CoreError> {
+        let mut reminder = ReminderState::new(name, wait_ms, duration_ms);
+        reminder.is_active = is_active;
+        validation::validate_reminder(&reminder, &self.state.reminders)?;
+        self.state.reminders.push(reminder.clone());
+
+        self.cache.sync_reminder(&reminder);
+        self.state.save()?;
+        Ok(reminder)
+    }
+
+    fn schedule_once(&mut self, name: String, target: &OnceTarget, duration_ms: i32, delete_when_done: bool) -> Result<ReminderState, CoreError> {
+        let now = Local::now();
+        let at = once::resolve(target, &now)?;
+        // the interval is never used, it only has to be valid
+        let wait_ms = i32::try_from((at - now.with_timezone(&Utc)).num_milliseconds()).unwrap_or(i32::MAX);
+        let mut reminder = ReminderState::new(name, wait_ms.clamp(validation::MIN_WAIT_MS, validation::MAX_WAIT_MS), duration_ms);
+        reminder.is_active = true;
+        reminder.once = Some(OneShot { at, delete_when_done, completed: false });
+        validation::validate_reminder(&reminder, &self.state.reminders)?;
+        self.state.reminders.push(reminder.clone());
+
+        self.cache.sync_reminder(&reminder);
+        self.state.save()?;
+        Ok(reminder)
     }
 
-    async fn start_reminder(&self, id: String, next_ms: i32) {
-        match self.event_sender.send(CoreEvent::ReminderNewStatus { id, next_duration_ms: next_ms }).await {
-            Ok(()) => {},
-            Err(e) => println!("Error: {:?}", e)
+    // snoozing a one-shot reminder opens it again for a single later firing,
+    // false for reminders on an interval
+    fn snooze_once(&mut self, id: &str, delay: i32) -> Result<bool, CoreError> {
+        let reminder = match self.state.reminders.iter_mut().find(|r| r.id == id) {
+            Some(r) if r.once.is_some() => r,
+            _ => return Ok(false)
+        };
+        if let Some(once) = reminder.once.as_mut() {
+            once.at = Utc::now() + chrono::Duration::milliseconds(i64::from(delay));
+            once.completed = false;
         }
+        reminder.is_active = true;
+        let reminder = reminder.clone();
+        self.cache.sync_reminder(&reminder);
+        self.state.save()?;
+        self.emit(CoreEvent::StateChanged { reminder_id: Some(reminder.id) });
+        Ok(true)
     }
 
+    // a one-shot reminder is done as soon as it fired
+    fn complete_once(&mut self, id: &str) {
+        let reminder = match self.state.reminders.iter_mut().find(|r| r.id == id) {
+            Some(r) => r,
+            None => return
+        };
+        if let Some(once) = reminder.once.as_mut() {
+            once.completed = true;
+        }
+        reminder.is_active = false;
+        self.cache.remove(id);
+        if let Err(e) = self.state.save() {
+            error!("could not store the completed reminder {}: {}", id, e);
+        }
+        self.emit(CoreEvent::StateChanged { reminder_id: Some(id.to_string()) });
+    }
+
+    // move an adaptive reminder's interval after a response, every change is
+    // announced with the reason so it never happens silently
+    fn adapt_interval(&mut self, id: &str) {
+        let reminder = match self.find_reminder(id) {
+            Some(r) if r.adaptive.is_some() => r.clone(),
+            _ => return
+        };
+        let now = Local::now();
+        let responses = adaptive::recent_responses(&self.history.entries(), id, now);
+        let adjustment = match adaptive::adjust(&reminder, &responses, now) {
+            Some(a) => a,
+            None => return
+        };
+        let previous_wait_ms = reminder.effective_wait_ms();
+        let mut adapted = reminder;
+        if let Some(a) = adapted.adaptive.as_mut() {
+            a.current_wait_ms = Some(adjustment.wait_ms);
+        }
+        if let Some(r) = self.state.reminders.iter_mut().find(|r| r.id == id) {
+            *r = adapted.clone();
+        }
+        self.cache.sync_reminder(&adapted);
+        self.state.save().unwrap_or_else(|e| error!("cannot save client state: {:?}", e));
+
+        info!("{}", adjustment.reason);
+        self.emit(CoreEvent::ReminderIntervalAdapted {
+            id: id.to_string(),
+            previous_wait_ms,
+            wait_ms: adjustment.wait_ms,
+            reason: adjustment.reason
+        });
+    }
+
+    // answers with today's intake
+    fn log_intake(&mut self, id: &str, amount_ml: Option<i32>) -> Result<IntakeDay, CoreError> {
+        let reminder = self.reminder(id)?;
+        let amount_ml = match (&reminder.kind, amount_ml) {
+            (ReminderKind::Hydration { .. }, Some(amount)) => amount,
+            (ReminderKind::Hydration { amount_ml }, None) => *amount_ml,
+            (ReminderKind::Break, _) => return Err(CoreError::Validation { field: "id".to_string(), reason: "is not a hydration reminder".to_string() })
+        };
+        validation::validate_intake(amount_ml)?;
+        self.history.record(id, HistoryKind::Intake { amount_ml });
+        self.notifier.close(id);
+
+        let today = Local::now().date_naive();
+        Ok(IntakeDay {
+            date: today.format("%Y-%m-%d").to_string(),
+            amount_ml: self.history.intake_on(today),
+            goal_ml: self.state.settings.hydration.daily_goal_ml
+        })
+    }
+
+    fn apply_preset(&mut self, id: &str) -> Result<ReminderState, CoreError> {
+        let reminder = presets::find(id).ok_or_else(|| CoreError::preset_not_found(id))?.reminder();
+        validation::validate_reminder(&reminder, &self.state.reminders)?;
+        self.state.reminders.push(reminder.clone());
+
+        self.cache.sync_reminder(&reminder);
+        self.state.save()?;
+        Ok(reminder)
+    }
+
+    fn finish_onboarding(&mut self, ids: Option<Vec<String>>) -> Result<Vec<ReminderState>, CoreError> {
+        if let Some(ids) = ids {
+            let reminders = ids
+                .iter()
+                .map(|id| presets::find(id).map(|p| p.reminder()).ok_or_else(|| CoreError::preset_not_found(id)))
+                .collect::<Result<Vec<ReminderState>, CoreError>>()?;
+            validation::validate_reminders(&reminders)?;
+            self.state.reminders = reminders;
+            self.cache.resync(&self.state.reminders);
+        }
+        self.state.onboarded = true;
+        self.state.save()?;
+        Ok(self.state.reminders.clone())
+    }
+
+    // only the patched reminder is rescheduled, other timers keep running
+    fn update_reminder(&mut self, id: &str, patch: ReminderPatch) -> Result<ReminderState, CoreError> {
+        let index = self.state.reminders
+            .iter()
+            .position(|r| r.id == id)
+            .ok_or_else(|| CoreError::reminder_not_found(id))?;
+        // patch a copy so an invalid patch leaves the reminder untouched
+        let mut reminder = self.state.reminders[index].clone();
+        patch.apply(&mut reminder);
+        validation::validate_reminder(&reminder, &self.state.reminders)?;
+        self.state.reminders[index] = reminder.clone();
+
+        self.cache.sync_reminder(&reminder);
+        self.state.save()?;
+        Ok(reminder)
+    }
+
+    fn delete_reminder(&mut self, id: &str) -> Result<ReminderState, CoreError> {
+        let index = self.state.reminders
+            .iter()
+            .position(|r| r.id == id)
+            .ok_or_else(|| CoreError::reminder_not_found(id))?;
+        let reminder = self.state.reminders.remove(index);
+
+        self.cache.delete(id);
+        self.in_progress.remove(id);
+        self.notifier.close(id);
+        self.state.save()?;
+        Ok(reminder)
+    }
+
+    // listed reminders move to the front in the given order, the rest keep theirs
+    fn reorder_reminders(&mut self, ids: &[String]) -> Result<Vec<ReminderState>, CoreError> {
+        if let Some(missing) = ids.iter().find(|id| self.find_reminder(id).is_none()) {
+            return Err(CoreError::reminder_not_found(missing))
+        }
+        let (mut ordered, rest): (Vec<ReminderState>, Vec<ReminderState>) = self.state.reminders
+            .drain(..)
+            .partition(|r| ids.contains(&r.id));
+        ordered.sort_by_key(|r| ids.iter().position(|id| *id == r.id));
+        ordered.extend(rest);
+        self.state.reminders = ordered;
+        self.state.save()?;
+        Ok(self.state.reminders.clone())
+    }
+
+    // pick up changes made to the client state file outside of the app, the
+    // core's own writes match the state in memory and change nothing
+    fn reload_state(&mut self) -> Result<CoreResponse, CoreError> {
+        let mut state = ClientState::new(&self.state.data_path);
+        state.read_disk()?;
+        // keep running with the current state until the file is fixed
+        validation::validate_reminders(&state.reminders)?;
+        validation::validate_profiles(&state.profiles)?;
+        validation::validate_settings(&state.settings)?;
+        state.data_path = self.state.data_path.clone();
+        let reminder_id = match watcher::diff(&self.state, &state) {
+            StateDiff::Unchanged => return Ok(CoreResponse::Success(())),
+            StateDiff::Reminder(id) => Some(id),
+            StateDiff::Other => None
+        };
+        info!("client state changed on disk, reloading");
+        self.replace_state(state);
+        self.emit(CoreEvent::StateChanged { reminder_id });
+        Ok(CoreResponse::Success(()))
+    }
+
+    // nothing is applied when the imported reminders are invalid
+    fn import_config(&mut self, content: &str, mode: ImportMode) -> Result<ImportReport, CoreError> {
+        let (state, report) = config::import(&self.state, config::parse(content)?, mode);
+        validation::validate_reminders(&state.reminders)?;
+        validation::validate_settings(&state.settings)?;
+        for c in &report.conflicts {
+            warn!("import skipped {} ({}): {}", c.entry, c.name, c.reason);
+        }
+        self.replace_state(state);
+        self.emit(CoreEvent::StateChanged { reminder_id: None });
+        self.state.save()?;
+        Ok(report)
+    }
+
+    fn create_profile(&mut self, name: String) -> Result<ProfileState, CoreError> {
+        let profile = ProfileState::new(name);
+        validation::validate_profile(&profile, &self.state.profiles)?;
+        self.state.profiles.push(profile.clone());
+        self.state.save()?;
+        Ok(profile)
+    }
+
+    fn update_profile(&mut self, id: &str, patch: ProfilePatch) -> Result<ProfileState, CoreError> {
+        let index = self.profile_index(id)?;
+        let mut profile = self.state.profiles[index].clone();
+        if let Some(name) = patch.name {
+            profile.name = name;
+        }
+        if let Some(rules) = patch.rules {
+            profile.rules = rules;
+        }
+        validation::validate_profile(&profile, &self.state.profiles)?;
+        self.state.profiles[index] = profile.clone();
+        self.state.save()?;
+        Ok(profile)
+    }
+
+    // the active profile's reminders are running, switch away before deleting it
+    fn delete_profile(&mut self, id: &str) -> Result<ProfileState, CoreError> {
+        let index = self.profile_index(id)?;
+        if self.state.active_profile == id {
+            return Err(CoreError::Validation { field: "id".to_string(), reason: "the active profile cannot be deleted".to_string() })
+        }
+        let profile = self.state.profiles.remove(index);
+        self.state.save()?;
+        Ok(profile)
+    }
+
+    // the timers of the old profile's reminders stop and the new ones start
+    fn switch_profile(&mut self, id: &str) -> Result<(), CoreError> {
+        if self.state.active_profile == id {
+            return self.profile_index(id).map(|_| ())
+        }
+        let mut state = self.state.clone();
+        profiles::switch(&mut state, id)?;
+        for id in self.in_progress.drain() {
+            self.notifier.close(&id);
+        }
+        self.replace_state(state);
+        info!("switched to profile {}", self.state.active_profile().map(|p| p.name.as_str()).unwrap_or_default());
+        self.emit(CoreEvent::StateChanged { reminder_id: None });
+        self.state.save()?;
+        Ok(())
+    }
+
+    fn apply_profile_rules(&mut self) {
+        let matched = profiles::matching_profile(&self.state.profiles, Local::now().naive_local()).map(|p| p.id.clone());
+        if matched == self.rule_profile {
+            return
+        }
+        self.rule_profile = matched.clone();
+        if let Some(id) = matched {
+            if let Err(error) = self.switch_profile(&id) {
+                warn!("cannot switch profiles: {}", error);
+                self.emit(CoreEvent::EngineError { reminder_id: None, error })
+            }
+        }
+    }
+
+    fn profile_index(&self, id: &str) -> Result<usize, CoreError> {
+        self.state.profiles
+            .iter()
+            .position(|p| p.id == id)
+            .ok_or_else(|| CoreError::profile_not_found(id))
+    }
+
+    fn replace_state(&mut self, state: ClientState) {
+        let was_paused = self.state.paused;
+        self.state = state;
+        self.logger.set_level(self.state.log_level);
+
+        self.cache.resync(&self.state.reminders);
+        if was_paused != self.state.paused {
+            self.cache.set_paused(self.state.paused);
+        }
+        // breaks of reminders which are gone now were cancelled along with them
+        let cancelled: Vec<String> = self.in_progress.iter().filter(|id| !self.cache.is_ending(id)).cloned().collect();
+        for id in cancelled {
+            self.in_progress.remove(&id);
+            self.notifier.close(&id);
+        }
+    }
+
+    // stop every reminder and flush what is still pending, start returns afterwards
+    async fn shutdown(&mut self) -> Result<CoreResponse, CoreError> {
+        info!("shutting down...");
+        self.stopped = true;
+        self.watcher = None;
+        self.emit(CoreEvent::ShuttingDown);
+        self.cache.stop();
+
+        // end breaks which are still running so their end hooks can undo what
+        // the start hooks did, e.g. restore a chat status
+        let in_progress: Vec<String> = self.in_progress.drain().collect();
+        for id in in_progress {
+            self.history.record(&id, HistoryKind::ReminderEnded);
+            let hooks = self.find_reminder(&id).and_then(|r| self.hook_commands(r, HOOK_EVENT_END));
+            if let Some((commands, ctx)) = hooks {
+                for command in commands {
+                    let output = hooks::run_hook(&command, &ctx, self.hook_timeout()).await;
+                    self.history.record(&id, HistoryKind::HookRan(output));
+                }
+            }
+        }
+
+        self.notifier.shutdown().await;
+        self.webhooks.shutdown().await;
+        self.history.flush().await;
+        self.state.save()?;
+        Ok(CoreResponse::Success(()))
+    }
+
+    async fn start_reminder(&mut self, id: String, mut next_ms: i32) {
+        let now = Local::now();
+        let reminder = self.find_reminder(&id).cloned();
+        let pace = match &reminder {
+            Some(r) if r.pending_once().is_none() && matches!(r.kind, ReminderKind::Hydration { .. }) => self.hydration_pace(next_ms, now),
+            _ => None
+        };
+        // a met goal skips the rest of the day without a break
+        if pace == Some(Pace::GoalMet) {
+            let resume = hydration::next_day_start(now.naive_local(), &self.state.settings.working_hours);
+            let wait_ms = Local.from_local_datetime(&resume).earliest()
+                .and_then(|at| i32::try_from((at - now).num_milliseconds()).ok())
+                .unwrap_or(validation::MAX_WAIT_MS);
+            debug!("daily goal met, next reminder in {} ms", wait_ms);
+            self.cache.delay_reminder(&id, wait_ms.clamp(1, validation::MAX_WAIT_MS));
+            return
+        }
+
+        self.history.record(&id, HistoryKind::ReminderStarted);
+        // outside of working hours the schedule carries on without interrupting anyone
+        let quiet = !self.state.settings.working_hours.contains(now.naive_local());
+        let mut message = ReminderMessage::default();
+        let mut exercises = Vec::new();
+        if let Some(reminder) = reminder {
+            if reminder.pending_once().is_some() {
+                self.complete_once(&id);
+            } else if let Some(Pace::Behind(wait_ms)) = pace {
+                debug!("behind the daily goal, next reminder in {} ms", wait_ms);
+                self.cache.delay_reminder(&id, wait_ms);
+                next_ms = wait_ms;
+            }
+            if !quiet {
+                self.in_progress.insert(id.clone());
+            }
+            let count_today = self.history.count_today(&id);
+            message = self.messages.render(&reminder, count_today, now);
+            exercises = self.exercises.pick(Library::bundled(), &reminder);
+            self.cache.schedule_end(&id, reminder.duration_ms);
+            if quiet {
+                debug!("outside of working hours, not interrupting");
+            } else {
+                self.run_hooks(&reminder, HOOK_EVENT_START);
+                self.notify(&reminder, &message);
+            }
+        }
+        self.emit(CoreEvent::ReminderNewStatus { id, next_duration_ms: next_ms, message, exercises })
+    }
+
+    // none while adaptive hydration reminders are turned off
+    fn hydration_pace(&self, interval_ms: i32, now: DateTime<Local>) -> Option<Pace> {
+        let settings = &self.state.settings;
+        if !settings.hydration.adaptive {
+            return None
+        }
+        let logged = self.history.intake_on(now.date_naive());
+        Some(hydration::pace(settings.hydration.daily_goal_ml, logged, interval_ms, now.naive_local(), &settings.working_hours))
+    }
+
+    async fn end_reminder(&mut self, id: String) {
+        self.cache.end_handled(&id);
+        self.history.record(&id, HistoryKind::ReminderEnded);
+        // end hooks only run when the start hooks did
+        if self.in_progress.remove(&id) {
+            if let Some(reminder) = self.find_reminder(&id) {
+                self.run_hooks(reminder, HOOK_EVENT_END);
+            }
+        }
+        self.notifier.close(&id);
+        self.emit(CoreEvent::ReminderEnd { id: id.clone() });
+
+        // unless it was snoozed in the meantime
+        let done = matches!(self.find_reminder(&id).and_then(|r| r.once.as_ref()), Some(o) if o.completed && o.delete_when_done);
+        if done {
+            match self.delete_reminder(&id) {
+                Ok(_) => self.emit(CoreEvent::StateChanged { reminder_id: Some(id) }),
+                Err(e) => error!("could not delete the completed reminder {}: {}", id, e)
+            }
+        }
+    }
+
+    fn notify(&self, reminder: &ReminderState, message: &ReminderMessage) {
+        if !self.state.notifications {
+            return
+        }
+        let settings = &self.state.settings.notification;
+        let timeout_ms = match settings.style {
+            NotificationStyle::Banner => reminder.duration_ms,
+            NotificationStyle::Persistent => 0
+        };
+        self.notifier.show(&reminder.id, &message.title, &message.body, timeout_ms, settings.sound);
+    }
+
+    // the global and the reminder's own hooks for the event, none while command hooks are disabled
+    fn hook_commands(&self, reminder: &ReminderState, event: &'static str) -> Option<(Vec<String>, HookContext)> {
+        let commands: Vec<String> = [&self.state.hooks, &reminder.hooks]
+            .iter()
+            .filter_map(|h| if event == HOOK_EVENT_START { h.on_start.clone() } else { h.on_end.clone() })
+            .filter(|c| !c.trim().is_empty())
+            .collect();
+        if commands.is_empty() {
+            return None
+        }
+        if !self.state.allow_command_hooks {
+            warn!("skipping {} hook(s), command hooks are disabled", commands.len());
+            return None
+        }
+
+        let ctx = HookContext {
+            event,
+            reminder_id: reminder.id.clone(),
+            reminder_name: reminder.name.clone(),
+            duration_ms: reminder.duration_ms
+        };
+        Some((commands, ctx))
+    }
+
+    fn hook_timeout(&self) -> std::time::Duration {
+        std::time::Duration::from_millis(u64::try_from(self.state.hook_timeout_ms).unwrap_or(0))
+    }
+
+    // run the reminder's hooks for the event in the background
+    fn run_hooks(&self, reminder: &ReminderState, event: &'static str) {
+        let (commands, ctx) = match self.hook_commands(reminder, event) {
+            Some(hooks) => hooks,
+            None => return
+        };
+        let timeout = self.hook_timeout();
+        for command in commands {
+            let ctx = ctx.clone();
+            let sender = self.internal_channel.0.clone();
+            tokio::spawn(async move {
+                let output = hooks::run_hook(&command, &ctx, timeout).await;
+                sender.send(InternalEvent::HookFinished { reminder_id: ctx.reminder_id, output }).unwrap_or(());
+            });
+        }
+    }
+
+    // send an event to every subscriber and every configured webhook
+    fn emit(&self, event: CoreEvent) {
+        self.dispatch_webhooks(&event);
+        self.events.publish(event);
+    }
+
+    // listen to the events matching the filter, e.g. before the core is started
+    pub fn subscribe(&self, filter: EventFilter) -> Subscription {
+        self.events.subscribe(filter)
+    }
+
+    fn dispatch_webhooks(&self, event: &CoreEvent) {
+        if self.state.webhooks.is_empty() || !self.state.settings.integrations.webhooks {
+            return
+        }
+
+        let now = Utc::now();
+        let payload = match event {
+            // only ever sent to a subscriber which fell behind
+            CoreEvent::Lagged { .. } => return,
+            CoreEvent::ReminderNewStatus { id, next_duration_ms, message, .. } => WebhookPayload {
+                reminder_id: id.clone(),
+                reminder_name: self.reminder_name(id),
+                event: event.name().to_string(),
+                occurred_at: now,
+                next_execution: Some(now + chrono::Duration::milliseconds(i64::from(*next_duration_ms))),
+                message: Some(message.clone()),
+                details: None
+            },
+            CoreEvent::ReminderEnd { id } => WebhookPayload {
+                reminder_id: id.clone(),
+                reminder_name: self.reminder_name(id),
+                event: event.name().to_string(),
+                occurred_at: now,
+                next_execution: self.cache.next_execution(id),
+                message: None,
+                details: None
+            },
+            // the reminder fields stay empty for events which are not about a single reminder
+            _ => {
+                let id = event.reminder_id();
+                WebhookPayload {
+                    reminder_id: id.unwrap_or_default().to_string(),
+                    reminder_name: id.map(|id| self.reminder_name(id)).unwrap_or_default(),
+                    event: event.name().to_string(),
+                    occurred_at: now,
+                    next_execution: id.and_then(|id| self.cache.next_execution(id)),
+                    message: None,
+                    details: Some(event.clone())
+                }
+            }
+        };
+        self.webhooks.enqueue(&self.state.webhooks, payload);
+    }
+
+    fn reminder_name(&self, id: &str) -> String {
+        self.find_reminder(id)
+            .map(|r| r.name.clone())
+            .unwrap_or_default()
+    }
+
+    fn reminder(&self, id: &str) -> Result<&ReminderState, CoreError> {
+        self.find_reminder(id).ok_or_else(|| CoreError::reminder_not_found(id))
+    }
+
+    fn find_reminder(&self, id: &str) -> Option<&ReminderState> {
+        self.state.reminders.iter().find(|r| r.id == id)
+    }
+
+}
+
+// the message a panic was raised with
+pub fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    match payload.downcast::<String>() {
+        Ok(s) => *s,
+        Err(payload) => payload
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .unwrap_or_else(|| "unknown panic".to_string())
+    }
 }
 
 // a wrapper around external input with a returning sender channel for core to respond
@@ -185,12 +1029,19 @@ pub struct ReturnableMessage<D, R = Result<CoreResponse, CoreError>> {
 }
 
 // core controller is passed to the client to communicate with the core which runs in a dedicated thread
+#[derive(Clone)]
 pub struct CoreController {
   query_tx: UnboundedSender<ReturnableMessage<ClientQuery>>,
   command_tx: UnboundedSender<ReturnableMessage<ClientCommand>>,
+  events: EventBus,
 }
 
 impl CoreController {
+    // listen to the events matching the filter from now on
+    pub fn subscribe(&self, filter: EventFilter) -> Subscription {
+        self.events.subscribe(filter)
+    }
+
     pub async fn query(&self, query: ClientQuery) -> Result<CoreResponse, CoreError> {
         
         let (tx, rx) = oneshot::channel();
@@ -199,10 +1050,16 @@ impl CoreController {
             tx_return: tx
         };
 
+        // both channels close once the core has stopped
         self.query_tx
             .send(payload)
-            .unwrap_or(());
-         rx.await.unwrap()
+            .map_err(|_| CoreError::Unavailable)?;
+        rx.await.unwrap_or(Err(CoreError::Unavailable))
+    }
+
+    // resolves once the core has flushed everything, it stops right after
+    pub async fn shutdown(&self) -> Result<CoreResponse, CoreError> {
+        self.command(ClientCommand::Shutdown).await
     }
 
     pub async fn command(&self, cmd: ClientCommand) -> Result<CoreResponse, CoreError> {
@@ -215,14 +1072,15 @@ impl CoreController {
 
         self.command_tx
             .send(payload)
-            .unwrap_or(());
-         rx.await.unwrap()
+            .map_err(|_| CoreError::Unavailable)?;
+        rx.await.unwrap_or(Err(CoreError::Unavailable))
     }
 }
 
 #[derive(Clone)]
 pub struct CoreContext {
-    pub intenal_sender: UnboundedSender<InternalEvent>
+    pub intenal_sender: UnboundedSender<InternalEvent>,
+    pub rng: SharedRng
 }
 
 
@@ -232,7 +1090,36 @@ pub struct CoreContext {
 #[ts(export)]
 pub enum ClientQuery {
     ClientGetState,
-    JobGetRunning
+    JobGetRunning,
+    WebhookGetDeliveries,
+    HistoryGet,
+    ReminderGetSchedule,
+    // the newest log records, oldest first, to attach to bug reports
+    GetRecentLogs{ limit: Option<usize> },
+    GetSettings,
+    // the bundled exercises and sets reminders can refer to
+    ExerciseGetLibrary,
+    PresetGetCatalogue,
+    // the water logged per day, today last
+    IntakeGetStats{ days: Option<u32> }
+}
+
+impl ClientQuery {
+    // the query's key, used to label its log records
+    pub fn name(&self) -> &'static str {
+        match self {
+            ClientQuery::ClientGetState => "ClientGetState",
+            ClientQuery::JobGetRunning => "JobGetRunning",
+            ClientQuery::WebhookGetDeliveries => "WebhookGetDeliveries",
+            ClientQuery::HistoryGet => "HistoryGet",
+            ClientQuery::ReminderGetSchedule => "ReminderGetSchedule",
+            ClientQuery::GetRecentLogs { .. } => "GetRecentLogs",
+            ClientQuery::GetSettings => "GetSettings",
+            ClientQuery::ExerciseGetLibrary => "ExerciseGetLibrary",
+            ClientQuery::PresetGetCatalogue => "PresetGetCatalogue",
+            ClientQuery::IntakeGetStats { .. } => "IntakeGetStats"
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, TS)]
@@ -242,7 +1129,73 @@ pub enum ClientCommand {
     AddOne { value: i32 },
     Add { x: i32, y: i32},
     SaveReminders{ reminders: Vec<ReminderState> },
-    DelayReminder{ id: String, delay: i32}
+    CreateReminder{ name: String, wait_ms: i32, duration_ms: i32, is_active: bool },
+    // a reminder firing once, completed afterwards and deleted too when asked to
+    ScheduleOnce{ name: String, target: OnceTarget, duration_ms: i32, delete_when_done: bool },
+    // boxed, a patch is much larger than the other commands
+    UpdateReminder{ id: String, patch: Box<ReminderPatch> },
+    DeleteReminder{ id: String },
+    SetReminderActive{ id: String, is_active: bool },
+    ReorderReminders{ ids: Vec<String> },
+    // stop all reminders, flush pending work and stop the core
+    Shutdown,
+    DelayReminder{ id: String, delay: i32},
+    SkipReminder{ id: String },
+    CompleteReminder{ id: String },
+    // log water drunk through a hydration reminder, its own amount when none is given
+    LogIntake{ id: String, amount_ml: Option<i32> },
+    SetPaused{ paused: bool },
+    ReloadState,
+    SetLogLevel{ level: LogLevel },
+    // replaces every setting, answers with the settings as stored
+    UpdateSettings{ settings: Settings },
+    CreateProfile{ name: String },
+    UpdateProfile{ id: String, patch: ProfilePatch },
+    DeleteProfile{ id: String },
+    // stores the current reminders with their profile and runs the other profile's
+    SwitchProfile{ id: String },
+    // adds the preset's reminder, turned on
+    ApplyPreset{ id: String },
+    // the chosen presets replace the suggested reminders, none keeps them
+    FinishOnboarding{ presets: Option<Vec<String>> },
+    // the client state as a human readable config file
+    ExportConfig{ format: ConfigFormat },
+    // the format is detected from the content
+    ImportConfig{ content: String, mode: ImportMode }
+}
+
+impl ClientCommand {
+    // the command's key, used to label its log records
+    pub fn name(&self) -> &'static str {
+        match self {
+            ClientCommand::AddOne { .. } => "AddOne",
+            ClientCommand::Add { .. } => "Add",
+            ClientCommand::SaveReminders { .. } => "SaveReminders",
+            ClientCommand::CreateReminder { .. } => "CreateReminder",
+            ClientCommand::ScheduleOnce { .. } => "ScheduleOnce",
+            ClientCommand::UpdateReminder { .. } => "UpdateReminder",
+            ClientCommand::DeleteReminder { .. } => "DeleteReminder",
+            ClientCommand::SetReminderActive { .. } => "SetReminderActive",
+            ClientCommand::ReorderReminders { .. } => "ReorderReminders",
+            ClientCommand::Shutdown => "Shutdown",
+            ClientCommand::DelayReminder { .. } => "DelayReminder",
+            ClientCommand::SkipReminder { .. } => "SkipReminder",
+            ClientCommand::CompleteReminder { .. } => "CompleteReminder",
+            ClientCommand::LogIntake { .. } => "LogIntake",
+            ClientCommand::SetPaused { .. } => "SetPaused",
+            ClientCommand::ReloadState => "ReloadState",
+            ClientCommand::SetLogLevel { .. } => "SetLogLevel",
+            ClientCommand::UpdateSettings { .. } => "UpdateSettings",
+            ClientCommand::CreateProfile { .. } => "CreateProfile",
+            ClientCommand::UpdateProfile { .. } => "UpdateProfile",
+            ClientCommand::DeleteProfile { .. } => "DeleteProfile",
+            ClientCommand::SwitchProfile { .. } => "SwitchProfile",
+            ClientCommand::ApplyPreset { .. } => "ApplyPreset",
+            ClientCommand::FinishOnboarding { .. } => "FinishOnboarding",
+            ClientCommand::ExportConfig { .. } => "ExportConfig",
+            ClientCommand::ImportConfig { .. } => "ImportConfig"
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, TS)]
@@ -250,33 +1203,576 @@ pub enum ClientCommand {
 #[ts(export)]
 pub enum CoreResponse {
     Success(()),
-    ClientGetState(ClientState),
+    // boxed, the client state is far larger than any other response
+    ClientGetState(Box<ClientState>),
+    WebhookGetDeliveries(Vec<WebhookDelivery>),
+    HistoryGet(Vec<HistoryEntry>),
+    ReminderGetSchedule(Vec<ReminderSchedule>),
+    Reminder(ReminderState),
+    Reminders(Vec<ReminderState>),
+    Profile(ProfileState),
+    Settings(Settings),
+    ExerciseLibrary(Library),
+    Presets(Vec<Preset>),
+    IntakeStats(Vec<IntakeDay>),
+    Logs(Vec<LogRecord>),
+    Config(String),
+    ConfigImported(ImportReport),
     Sum(i32)
 }
 
-#[derive(Error, Debug)]
+// every error a client can see, the code is stable for programs to match on
+// while the message is meant for people
+#[derive(Error, Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
+#[serde(tag = "code", content = "details")]
+#[ts(export)]
 pub enum CoreError {
-  #[error("Query error")]
-  QueryError,
-//   #[error("System error")]
-//   SysError(#[from] sys::SysError),
-//   #[error("File error")]
-//   FileError(#[from] file::FileError),
-//   #[error("Job error")]
-//   JobError(#[from] job::JobError),
-//   #[error("Database error")]
-//   DatabaseError(#[from] prisma::QueryError),
-//   #[error("Database error")]
-//   LibraryError(#[from] library::LibraryError),
+  // reading or writing files in the data directory failed
+  #[serde(rename = "storage")]
+  #[error("Storage error: {reason}")]
+  Storage { reason: String },
+  #[serde(rename = "validation")]
+  #[error("Invalid {field}: {reason}")]
+  Validation { field: String, reason: String },
+  #[serde(rename = "not_found")]
+  #[error("No {resource} with id {id}")]
+  NotFound { resource: String, id: String },
+  // webhooks, notifications, hooks and the other outside services
+  #[serde(rename = "integration")]
+  #[error("{integration} error: {reason}")]
+  Integration { integration: String, reason: String },
+  // a reminder timer could not be scheduled
+  #[serde(rename = "scheduler")]
+  #[error("Scheduler error: {reason}")]
+  Scheduler { reason: String },
+  // a client sent something which is not a query or command
+  #[serde(rename = "invalid_request")]
+  #[error("Invalid request: {reason}")]
+  InvalidRequest { reason: String },
+  // handling the request panicked, the core itself keeps running
+  #[serde(rename = "internal")]
+  #[error("Internal error: {reason}")]
+  Internal { reason: String },
+  // the core has stopped and no longer answers
+  #[serde(rename = "unavailable")]
+  #[error("The core is not running")]
+  Unavailable
+}
+
+impl CoreError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            CoreError::Storage { .. } => "storage",
+            CoreError::Validation { .. } => "validation",
+            CoreError::NotFound { .. } => "not_found",
+            CoreError::Integration { .. } => "integration",
+            CoreError::Scheduler { .. } => "scheduler",
+            CoreError::InvalidRequest { .. } => "invalid_request",
+            CoreError::Internal { .. } => "internal",
+            CoreError::Unavailable => "unavailable"
+        }
+    }
+
+    pub fn reminder_not_found(id: &str) -> CoreError {
+        CoreError::NotFound { resource: "reminder".to_string(), id: id.to_string() }
+    }
+
+    pub fn profile_not_found(id: &str) -> CoreError {
+        CoreError::NotFound { resource: "profile".to_string(), id: id.to_string() }
+    }
+
+    pub fn preset_not_found(id: &str) -> CoreError {
+        CoreError::NotFound { resource: "preset".to_string(), id: id.to_string() }
+    }
+}
+
+impl From<std::io::Error> for CoreError {
+    fn from(e: std::io::Error) -> Self {
+        CoreError::Storage { reason: e.to_string() }
+    }
 }
 
 #[derive(Debug, PartialEq)]
 pub enum InternalEvent {
-    ReminderStart{ id: String, next_duration_ms: i32 } 
+    ReminderStart{ id: String, next_duration_ms: i32 },
+    ReminderEnd{ id: String },
+    HookFinished{ reminder_id: String, output: HookOutput },
+    NotificationAction{ reminder_id: String, action: NotificationAction },
+    // a reminder's timer panicked, the cache restarts it a few times
+    ReminderTaskFailed{ id: String, reason: String },
+    // the client state file was written, by the core itself or someone else
+    StateFileChanged,
+    // time to check whether a profile's switching rules started to apply
+    ProfileRulesDue
+}
+
+impl InternalEvent {
+    pub fn reminder_id(&self) -> Option<&str> {
+        match self {
+            InternalEvent::ReminderStart { id, .. } | InternalEvent::ReminderEnd { id } | InternalEvent::ReminderTaskFailed { id, .. } => Some(id),
+            InternalEvent::HookFinished { reminder_id, .. } | InternalEvent::NotificationAction { reminder_id, .. } => Some(reminder_id),
+            InternalEvent::StateFileChanged | InternalEvent::ProfileRulesDue => None
+        }
+    }
 }
 
-#[derive(Serialize, Deserialize, Debug, TS, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, TS, PartialEq)]
 #[ts(export)]
 pub enum CoreEvent {
-    ReminderNewStatus{ id: String, next_duration_ms: i32 } 
+    // the message and exercises are picked once so every surface shows the same
+    ReminderNewStatus{ id: String, next_duration_ms: i32, message: ReminderMessage, exercises: Vec<Exercise> },
+    ReminderEnd{ id: String },
+    // an adaptive reminder's interval was moved, the reason can be shown as is
+    ReminderIntervalAdapted{ id: String, previous_wait_ms: i32, wait_ms: i32, reason: String },
+    // reminders were created, edited, removed or reordered
+    StateChanged{ reminder_id: Option<String> },
+    // something went wrong inside the core, it keeps running
+    EngineError{ reminder_id: Option<String>, error: CoreError },
+    // the last event before the core stops
+    ShuttingDown,
+    // only sent to a subscriber which fell behind, it missed this many events
+    Lagged{ skipped: u32 }
+}
+
+impl CoreEvent {
+    // a stable name for the kind of event, used by integrations
+    pub fn name(&self) -> &'static str {
+        match self {
+            CoreEvent::ReminderNewStatus { .. } => "ReminderNewStatus",
+            CoreEvent::ReminderEnd { .. } => "ReminderEnd",
+            CoreEvent::ReminderIntervalAdapted { .. } => "ReminderIntervalAdapted",
+            CoreEvent::StateChanged { .. } => "StateChanged",
+            CoreEvent::EngineError { .. } => "EngineError",
+            CoreEvent::ShuttingDown => "ShuttingDown",
+            CoreEvent::Lagged { .. } => "Lagged"
+        }
+    }
+
+    // the reminder an event is about, if it is about a single one
+    pub fn reminder_id(&self) -> Option<&str> {
+        match self {
+            CoreEvent::ReminderNewStatus { id, .. } | CoreEvent::ReminderEnd { id } | CoreEvent::ReminderIntervalAdapted { id, .. } => Some(id),
+            CoreEvent::StateChanged { reminder_id } | CoreEvent::EngineError { reminder_id, .. } => reminder_id.as_deref(),
+            CoreEvent::ShuttingDown | CoreEvent::Lagged { .. } => None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ClientCommand, ClientQuery, Core, CoreError, CoreEvent, CoreResponse, InternalEvent, events::{EventFilter, Subscription}, once::OnceTarget, state::{AdaptiveInterval, ClientState, ReminderKind, ReminderPatch}};
+
+    fn create_test_core() -> (std::path::PathBuf, Core, Subscription) {
+        let data_dir = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+        let core = Core::new(data_dir.clone());
+        let events = core.subscribe(EventFilter::default());
+        (data_dir, core, events)
+    }
+
+    fn into_reminder(res: Result<CoreResponse, CoreError>) -> crate::state::ReminderState {
+        match res {
+            Ok(CoreResponse::Reminder(r)) => r,
+            other => panic!("expected a reminder but got {:?}", other)
+        }
+    }
+
+    #[tokio::test]
+    async fn controller_errors_once_core_stopped() {
+        let (data_dir, core, _events) = create_test_core();
+        let controller = core.get_controller();
+        drop(core);
+
+        let res = controller.query(ClientQuery::ClientGetState).await;
+        std::fs::remove_dir_all(&data_dir).unwrap_or(());
+        assert!(matches!(res, Err(CoreError::Unavailable)));
+    }
+
+    #[tokio::test]
+    async fn reminder_crud_commands() {
+        let (data_dir, mut core, mut events) = create_test_core();
+        let existing = core.state.reminders.len();
+
+        // create
+        let created = into_reminder(core.exec_command(ClientCommand::CreateReminder {
+            name: "Walk".to_string(), wait_ms: 60 * 1000, duration_ms: 1000, is_active: true
+        }).await);
+        assert_eq!(core.state.reminders.len(), existing + 1);
+        assert_eq!(events.recv().await, Some(CoreEvent::StateChanged { reminder_id: Some(created.id.clone()) }));
+
+        // patch only touches the given fields
+        let patch = ReminderPatch { name: Some("Long walk".to_string()), ..ReminderPatch::default() };
+        let updated = into_reminder(core.exec_command(ClientCommand::UpdateReminder { id: created.id.clone(), patch: Box::new(patch) }).await);
+        assert_eq!(updated.name, "Long walk");
+        assert_eq!(updated.wait_ms, created.wait_ms);
+        assert!(updated.is_active);
+
+        let paused = into_reminder(core.exec_command(ClientCommand::SetReminderActive { id: created.id.clone(), is_active: false }).await);
+        assert!(!paused.is_active);
+
+        // an invalid patch is rejected and leaves the reminder as it was
+        let patch = ReminderPatch { wait_ms: Some(-1), ..ReminderPatch::default() };
+        let invalid = core.exec_command(ClientCommand::UpdateReminder { id: created.id.clone(), patch: Box::new(patch) }).await;
+        assert!(matches!(invalid, Err(CoreError::Validation { field, .. }) if field == "wait_ms"));
+        assert_eq!(core.find_reminder(&created.id).unwrap().wait_ms, created.wait_ms);
+
+        // reorder moves the listed reminders to the front
+        match core.exec_command(ClientCommand::ReorderReminders { ids: vec![created.id.clone()] }).await {
+            Ok(CoreResponse::Reminders(r)) => assert_eq!(r[0].id, created.id),
+            other => panic!("expected reminders but got {:?}", other)
+        }
+
+        // delete
+        let deleted = into_reminder(core.exec_command(ClientCommand::DeleteReminder { id: created.id.clone() }).await);
+        assert_eq!(deleted.id, created.id);
+        assert_eq!(core.state.reminders.len(), existing);
+
+        let missing = core.exec_command(ClientCommand::DeleteReminder { id: created.id.clone() }).await;
+        std::fs::remove_dir_all(&data_dir).unwrap_or(());
+        assert_eq!(missing.unwrap_err().code(), "not_found");
+    }
+
+    #[tokio::test]
+    async fn update_settings_is_validated_and_saved() {
+        let (data_dir, mut core, _events) = create_test_core();
+        let mut settings = core.settings().clone();
+        settings.working_hours.enabled = true;
+        settings.locale = "de-AT".to_string();
+
+        let res = core.exec_command(ClientCommand::UpdateSettings { settings: settings.clone() }).await;
+        assert!(matches!(res, Ok(CoreResponse::Settings(s)) if s == settings));
+
+        let mut invalid = settings.clone();
+        invalid.working_hours.end = "late".to_string();
+        let res = core.exec_command(ClientCommand::UpdateSettings { settings: invalid }).await;
+        assert!(matches!(res, Err(CoreError::Validation { field, .. }) if field == "working_hours.end"));
+
+        // the rejected update left the saved settings alone
+        let reopened = Core::new(data_dir.clone());
+        std::fs::remove_dir_all(&data_dir).unwrap_or(());
+        assert_eq!(reopened.settings(), &settings);
+    }
+
+    #[tokio::test]
+    async fn onboarding_applies_presets() {
+        let (data_dir, mut core, mut events) = create_test_core();
+        assert!(!core.state.onboarded);
+        assert!(core.state.reminders.iter().all(|r| !r.is_active));
+
+        let missing = core.exec_command(ClientCommand::FinishOnboarding { presets: Some(vec!["missing".to_string()]) }).await;
+        assert_eq!(missing.unwrap_err().code(), "not_found");
+        assert!(!core.state.onboarded);
+
+        let presets = Some(vec!["stand-up".to_string(), "posture".to_string()]);
+        match core.exec_command(ClientCommand::FinishOnboarding { presets }).await {
+            Ok(CoreResponse::Reminders(r)) => assert_eq!(r.iter().map(|r| r.name.as_str()).collect::<Vec<&str>>(), ["Hourly stand-up", "Posture check"]),
+            other => panic!("expected reminders but got {:?}", other)
+        }
+        assert_eq!(events.recv().await, Some(CoreEvent::StateChanged { reminder_id: None }));
+
+        let hydration = into_reminder(core.exec_command(ClientCommand::ApplyPreset { id: "hydration".to_string() }).await);
+        assert!(hydration.is_active);
+        // the same preset twice would give two reminders with the same name
+        let again = core.exec_command(ClientCommand::ApplyPreset { id: "hydration".to_string() }).await;
+
+        let mut reloaded = ClientState::new(core.state.data_path.as_str());
+        reloaded.read_disk().unwrap();
+        std::fs::remove_dir_all(&data_dir).unwrap_or(());
+
+        assert!(matches!(again, Err(CoreError::Validation { .. })));
+        assert!(reloaded.onboarded);
+        assert_eq!(reloaded.reminders.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn intake_is_logged_per_day() {
+        let (data_dir, mut core, _events) = create_test_core();
+        // the suggested reminders are the eye rule and hydration
+        let eyes = core.state.reminders[0].id.clone();
+        let water = core.state.reminders[1].id.clone();
+
+        core.exec_command(ClientCommand::LogIntake { id: water.clone(), amount_ml: None }).await.unwrap();
+        core.exec_command(ClientCommand::CompleteReminder { id: water.clone() }).await.unwrap();
+        let logged = core.exec_command(ClientCommand::LogIntake { id: water.clone(), amount_ml: Some(100) }).await;
+        let zero = core.exec_command(ClientCommand::LogIntake { id: water.clone(), amount_ml: Some(0) }).await;
+        let too_much = core.exec_command(ClientCommand::LogIntake { id: water, amount_ml: Some(crate::validation::MAX_INTAKE_ML + 1) }).await;
+        let not_water = core.exec_command(ClientCommand::LogIntake { id: eyes, amount_ml: None }).await;
+        let stats = core.exec_query(ClientQuery::IntakeGetStats { days: Some(2) }).await;
+        std::fs::remove_dir_all(&data_dir).unwrap_or(());
+
+        match logged {
+            Ok(CoreResponse::IntakeStats(days)) => assert_eq!((days[0].amount_ml, days[0].goal_ml), (600, 2000)),
+            other => panic!("expected intake but got {:?}", other)
+        }
+        assert_eq!(zero.unwrap_err().code(), "validation");
+        assert_eq!(too_much.unwrap_err().code(), "validation");
+        assert_eq!(not_water.unwrap_err().code(), "validation");
+        match stats {
+            Ok(CoreResponse::IntakeStats(days)) => assert_eq!(days.iter().map(|d| d.amount_ml).collect::<Vec<i32>>(), [0, 600]),
+            other => panic!("expected intake but got {:?}", other)
+        }
+    }
+
+    #[tokio::test]
+    async fn skipped_reminders_back_off() {
+        let (data_dir, mut core, mut events) = create_test_core();
+        let mut reminders = core.state.reminders.clone();
+        reminders[0].adaptive = Some(AdaptiveInterval { min_wait_ms: 20 * 60 * 1000, max_wait_ms: 40 * 60 * 1000, current_wait_ms: None });
+        let id = reminders[0].id.clone();
+        core.exec_command(ClientCommand::SaveReminders { reminders }).await.unwrap();
+        assert_eq!(events.recv().await, Some(CoreEvent::StateChanged { reminder_id: None }));
+
+        for _ in 0..3 {
+            core.exec_command(ClientCommand::SkipReminder { id: id.clone() }).await.unwrap();
+        }
+        let event = events.recv().await;
+        std::fs::remove_dir_all(&data_dir).unwrap_or(());
+
+        match event {
+            Some(CoreEvent::ReminderIntervalAdapted { id: adapted, previous_wait_ms, wait_ms, reason }) => {
+                assert_eq!(adapted, id);
+                assert_eq!((previous_wait_ms, wait_ms), (20 * 60 * 1000, 25 * 60 * 1000));
+                assert!(reason.contains("skipped 3 of the last 3 times"));
+            },
+            other => panic!("expected an adapted interval but got {:?}", other)
+        }
+        assert_eq!(core.find_reminder(&id).unwrap().effective_wait_ms(), 25 * 60 * 1000);
+    }
+
+    // a response to an unknown reminder fails and leaves no trace in the history
+    async fn assert_unknown_reminder(command: ClientCommand) {
+        let (data_dir, mut core, _events) = create_test_core();
+        let res = core.exec_command(command).await;
+        let entries = core.history.entries();
+        std::fs::remove_dir_all(&data_dir).unwrap_or(());
+        assert!(matches!(res, Err(CoreError::NotFound { id, .. }) if id == "missing"));
+        assert!(entries.is_empty());
+    }
+
+    #[tokio::test]
+    async fn delay_rejects_unknown_reminders() {
+        assert_unknown_reminder(ClientCommand::DelayReminder { id: "missing".to_string(), delay: 60 * 1000 }).await;
+    }
+
+    #[tokio::test]
+    async fn skip_rejects_unknown_reminders() {
+        assert_unknown_reminder(ClientCommand::SkipReminder { id: "missing".to_string() }).await;
+    }
+
+    #[tokio::test]
+    async fn complete_rejects_unknown_reminders() {
+        assert_unknown_reminder(ClientCommand::CompleteReminder { id: "missing".to_string() }).await;
+    }
+
+    #[tokio::test]
+    async fn met_hydration_goal_skips_the_break() {
+        let (data_dir, mut core, mut events) = create_test_core();
+        // the suggested hydration reminder
+        let water = core.state.reminders.iter().find(|r| r.kind != ReminderKind::Break).unwrap().clone();
+        let goal = core.state.settings.hydration.daily_goal_ml;
+        core.exec_command(ClientCommand::SetReminderActive { id: water.id.clone(), is_active: true }).await.unwrap();
+        assert_eq!(events.recv().await, Some(CoreEvent::StateChanged { reminder_id: Some(water.id.clone()) }));
+        core.exec_command(ClientCommand::LogIntake { id: water.id.clone(), amount_ml: Some(goal) }).await.unwrap();
+
+        core.exec_event(InternalEvent::ReminderStart { id: water.id.clone(), next_duration_ms: water.wait_ms }).await;
+        let event = tokio::time::timeout(std::time::Duration::from_millis(100), events.recv()).await;
+        let started = core.history.count_today(&water.id);
+        let tomorrow = core.cache.next_execution(&water.id).unwrap();
+
+        // pausing does not move the next reminder any further out
+        core.exec_command(ClientCommand::SetPaused { paused: true }).await.unwrap();
+        core.exec_command(ClientCommand::SetPaused { paused: false }).await.unwrap();
+        let resumed = core.cache.next_execution(&water.id).unwrap();
+        std::fs::remove_dir_all(&data_dir).unwrap_or(());
+
+        assert!(event.is_err(), "expected no event but got {:?}", event);
+        assert_eq!(started, 0);
+        assert!((resumed - tomorrow).num_milliseconds().abs() < 1000, "moved from {} to {}", tomorrow, resumed);
+    }
+
+    #[tokio::test]
+    async fn one_shot_reminders_fire_once() {
+        let (data_dir, mut core, mut events) = create_test_core();
+        let gym = into_reminder(core.exec_command(ClientCommand::ScheduleOnce {
+            name: "Leave for the gym".to_string(), target: OnceTarget::In { delay_ms: 50 }, duration_ms: 50, delete_when_done: true
+        }).await);
+        assert_eq!(events.recv().await, Some(CoreEvent::StateChanged { reminder_id: Some(gym.id.clone()) }));
+
+        // completed and turned off as soon as it fires
+        let start = core.internal_channel.1.recv().await.unwrap();
+        assert_eq!(start, InternalEvent::ReminderStart { id: gym.id.clone(), next_duration_ms: 50 });
+        core.exec_event(start).await;
+        let fired = core.find_reminder(&gym.id).unwrap().clone();
+        assert!(!fired.is_active && fired.once.unwrap().completed);
+        assert!(core.cache.next_execution(&gym.id).is_none());
+        let again = core.exec_command(ClientCommand::SetReminderActive { id: gym.id.clone(), is_active: true }).await;
+        assert!(matches!(again, Err(CoreError::Validation { .. })));
+
+        // and deleted once its break ended
+        let end = core.internal_channel.1.recv().await.unwrap();
+        core.exec_event(end).await;
+        assert!(core.find_reminder(&gym.id).is_none());
+
+        // snoozing opens it again for one more time
+        let call = into_reminder(core.exec_command(ClientCommand::ScheduleOnce {
+            name: "Call back".to_string(), target: OnceTarget::In { delay_ms: 50 }, duration_ms: 50, delete_when_done: false
+        }).await);
+        let start = core.internal_channel.1.recv().await.unwrap();
+        core.exec_event(start).await;
+        core.exec_command(ClientCommand::DelayReminder { id: call.id.clone(), delay: 60 * 1000 }).await.unwrap();
+        std::fs::remove_dir_all(&data_dir).unwrap_or(());
+        let snoozed = core.find_reminder(&call.id).unwrap();
+        assert!(snoozed.is_active);
+        assert!(snoozed.pending_once().unwrap() > call.once.unwrap().at);
+        assert!(core.cache.next_execution(&call.id).is_some());
+    }
+
+    #[tokio::test]
+    async fn skipped_and_deleted_breaks_do_not_end_later() {
+        let (data_dir, mut core, _events) = create_test_core();
+        let mut ends = core.subscribe(EventFilter { events: vec!["ReminderEnd".to_string()], ..EventFilter::default() });
+        let create = |name: &str| ClientCommand::CreateReminder { name: name.to_string(), wait_ms: 60 * 1000, duration_ms: 50, is_active: true };
+        let walk = into_reminder(core.exec_command(create("Walk")).await);
+        let tea = into_reminder(core.exec_command(create("Tea")).await);
+        for r in [&walk, &tea] {
+            core.exec_event(InternalEvent::ReminderStart { id: r.id.clone(), next_duration_ms: r.wait_ms }).await;
+            assert!(core.cache.is_ending(&r.id));
+        }
+
+        // skipping a running break ends it straight away
+        core.exec_command(ClientCommand::SkipReminder { id: walk.id.clone() }).await.unwrap();
+        core.exec_command(ClientCommand::DeleteReminder { id: tea.id.clone() }).await.unwrap();
+        let late = tokio::time::timeout(std::time::Duration::from_millis(200), core.internal_channel.1.recv()).await;
+        let ended = ends.recv().await;
+        let more = tokio::time::timeout(std::time::Duration::from_millis(50), ends.recv()).await;
+        std::fs::remove_dir_all(&data_dir).unwrap_or(());
+
+        assert!(late.is_err(), "got {:?}", late);
+        assert_eq!(ended, Some(CoreEvent::ReminderEnd { id: walk.id.clone() }));
+        assert!(more.is_err(), "got {:?}", more);
+        assert!(!core.cache.is_ending(&walk.id) && !core.cache.is_ending(&tea.id));
+    }
+
+    #[tokio::test]
+    async fn switch_profile_swaps_reminders() {
+        let (data_dir, mut core, mut events) = create_test_core();
+        let default_id = core.state.active_profile.clone();
+        let default_reminders = core.state.reminders.len();
+
+        let focus = match core.exec_command(ClientCommand::CreateProfile { name: "Focus".to_string() }).await {
+            Ok(CoreResponse::Profile(p)) => p,
+            other => panic!("expected a profile but got {:?}", other)
+        };
+        assert_eq!(events.recv().await, Some(CoreEvent::StateChanged { reminder_id: None }));
+
+        core.exec_command(ClientCommand::SwitchProfile { id: focus.id.clone() }).await.unwrap();
+        assert_eq!(events.recv().await, Some(CoreEvent::StateChanged { reminder_id: None }));
+        assert!(core.state.reminders.is_empty());
+        into_reminder(core.exec_command(ClientCommand::CreateReminder {
+            name: "Deep work".to_string(), wait_ms: 60 * 1000, duration_ms: 1000, is_active: true
+        }).await);
+
+        // the active profile cannot be deleted
+        let res = core.exec_command(ClientCommand::DeleteProfile { id: focus.id.clone() }).await;
+        assert!(matches!(res, Err(CoreError::Validation { .. })));
+
+        core.exec_command(ClientCommand::SwitchProfile { id: default_id }).await.unwrap();
+        let missing = core.exec_command(ClientCommand::SwitchProfile { id: "missing".to_string() }).await;
+        std::fs::remove_dir_all(&data_dir).unwrap_or(());
+
+        assert_eq!(core.state.reminders.len(), default_reminders);
+        assert_eq!(core.state.profiles[1].reminders[0].name, "Deep work");
+        assert_eq!(missing.unwrap_err().code(), "not_found");
+    }
+
+    #[tokio::test]
+    async fn edited_state_file_is_reloaded() {
+        let (data_dir, mut core, mut events) = create_test_core();
+        core.watch_state();
+        let controller = core.get_controller();
+        let path = std::path::Path::new(&core.state.data_path).join(crate::state::CLIENT_STATE_CONFIG_NAME);
+        let mut edited = core.state.clone();
+        tokio::spawn(async move { core.start().await });
+
+        // the core's own write does not count as an edit
+        controller.command(ClientCommand::SetPaused { paused: false }).await.unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+
+        edited.reminders[0].name = "Edited".to_string();
+        std::fs::write(&path, serde_json::to_string(&edited).unwrap()).unwrap();
+        let event = tokio::time::timeout(std::time::Duration::from_secs(5), events.recv()).await.unwrap();
+        let state = controller.query(ClientQuery::ClientGetState).await;
+        std::fs::remove_dir_all(&data_dir).unwrap_or(());
+
+        assert_eq!(event, Some(CoreEvent::StateChanged { reminder_id: Some(edited.reminders[0].id.clone()) }));
+        match state {
+            Ok(CoreResponse::ClientGetState(s)) => assert_eq!(s.reminders[0].name, "Edited"),
+            other => panic!("expected the state but got {:?}", other)
+        }
+    }
+
+    #[tokio::test]
+    async fn own_saves_are_not_reloaded() {
+        let (data_dir, mut core, mut events) = create_test_core();
+        core.watch_state();
+        let state = core.state.clone();
+        tokio::spawn(async move { core.start().await });
+
+        // the watcher never catches a save half way
+        for _ in 0..20 {
+            state.save().unwrap();
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+        let event = tokio::time::timeout(std::time::Duration::from_millis(1000), events.recv()).await;
+        std::fs::remove_dir_all(&data_dir).unwrap_or(());
+
+        assert!(event.is_err(), "got {:?}", event);
+    }
+
+    #[tokio::test]
+    async fn invalid_state_file_is_not_reloaded() {
+        let (data_dir, mut core, _events) = create_test_core();
+        let path = std::path::Path::new(&core.state.data_path).join(crate::state::CLIENT_STATE_CONFIG_NAME);
+        let mut edited = core.state.clone();
+        edited.profiles.push(edited.profiles[0].clone());
+        std::fs::write(&path, serde_json::to_string(&edited).unwrap()).unwrap();
+
+        let res = core.exec_command(ClientCommand::ReloadState).await;
+        std::fs::remove_dir_all(&data_dir).unwrap_or(());
+
+        assert!(matches!(res, Err(CoreError::Validation { field, .. }) if field == "profiles[1].id"));
+        assert_eq!(core.state.profiles.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn every_event_is_sent_to_webhooks() {
+        let (data_dir, mut core, _events) = create_test_core();
+        core.state.webhooks.push(crate::state::WebhookState::new("http://127.0.0.1:9/hook".to_string(), "secret".to_string()));
+        let id = core.state.reminders[0].id.clone();
+
+        core.exec_command(ClientCommand::SetReminderActive { id: id.clone(), is_active: true }).await.unwrap();
+        core.emit(CoreEvent::ShuttingDown);
+        let deliveries = core.webhooks.deliveries();
+        std::fs::remove_dir_all(&data_dir).unwrap_or(());
+
+        let payloads: Vec<_> = deliveries.iter().map(|d| (d.payload.event.as_str(), d.payload.reminder_id.as_str())).collect();
+        assert_eq!(payloads, vec![("StateChanged", id.as_str()), ("ShuttingDown", "")]);
+        assert_eq!(deliveries[0].payload.details, Some(CoreEvent::StateChanged { reminder_id: Some(id.clone()) }));
+        assert_eq!(deliveries[0].payload.reminder_name, core.state.reminders[0].name);
+    }
+
+    #[tokio::test]
+    async fn shutdown_stops_core() {
+        let (data_dir, mut core, mut events) = create_test_core();
+        let controller = core.get_controller();
+        let task = tokio::spawn(async move { core.start().await });
+
+        let res = controller.shutdown().await;
+        tokio::time::timeout(std::time::Duration::from_secs(5), task).await.unwrap().unwrap();
+        let after = controller.query(ClientQuery::ClientGetState).await;
+        std::fs::remove_dir_all(&data_dir).unwrap_or(());
+
+        assert!(matches!(res, Ok(CoreResponse::Success(_))));
+        assert_eq!(events.recv().await, Some(CoreEvent::ShuttingDown));
+        assert!(matches!(after, Err(CoreError::Unavailable)));
+    }
 }
\ No newline at end of file
@@ -0,0 +1,230 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::{collections::VecDeque, fmt::Write, path::Path, sync::{Arc, Mutex}};
+use tracing::{field::{Field, Visit}, span, Event, Level, Subscriber};
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::{
+    filter::{LevelFilter, Targets},
+    layer::{Context, SubscriberExt},
+    registry::LookupSpan,
+    reload, Layer, Registry,
+};
+use ts_rs::TS;
+
+use crate::state::LogLevel;
+
+// rotated log files live in a sub directory of the data directory
+pub static LOG_DIR_NAME: &str = "logs";
+static LOG_FILE_PREFIX: &str = "paz";
+// one file per day, a week of them is kept
+const MAX_LOG_FILES: usize = 7;
+// records kept in memory for GetRecentLogs
+const RECENT_LIMIT: usize = 1000;
+// the crates whose logs follow the configured level, everything else only logs warnings
+const OWN_TARGETS: [&str; 3] = ["pazcore", "pazd", "app"];
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
+#[ts(export)]
+pub struct LogRecord {
+  pub timestamp: DateTime<Utc>,
+  pub level: LogLevel,
+  pub target: String,
+  // the spans the record was logged in, outermost first, e.g. command{key=SaveReminders}
+  pub spans: Vec<String>,
+  pub message: String
+}
+
+// a handle to the process wide logger, the first core to start sets it up
+#[derive(Clone)]
+pub struct Logger {
+    recent: Arc<Mutex<VecDeque<LogRecord>>>,
+    filter: reload::Handle<Targets, Registry>
+}
+
+static LOGGER: Mutex<Option<Logger>> = Mutex::new(None);
+
+impl Logger {
+
+    // log to stdout, a rotating file in the data directory and memory
+    pub fn init(data_dir: &str, level: LogLevel) -> Logger {
+        let mut logger = LOGGER.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(l) = logger.as_ref() {
+            l.set_level(level);
+            return l.clone()
+        }
+
+        let (filter, handle) = reload::Layer::new(targets(level));
+        let recent = Arc::new(Mutex::new(VecDeque::new()));
+        let file = RollingFileAppender::builder()
+            .rotation(Rotation::DAILY)
+            .filename_prefix(LOG_FILE_PREFIX)
+            .filename_suffix("log")
+            .max_log_files(MAX_LOG_FILES)
+            .build(Path::new(data_dir).join(LOG_DIR_NAME));
+        let file_error = file.as_ref().err().map(|e| e.to_string());
+
+        let subscriber = Registry::default()
+            .with(filter)
+            .with(tracing_subscriber::fmt::layer())
+            .with(file.ok().map(|f| tracing_subscriber::fmt::layer().with_ansi(false).with_writer(f)))
+            .with(RecentLayer { recent: recent.clone() });
+        // another subscriber may already be installed, e.g. by an embedding app
+        if tracing::subscriber::set_global_default(subscriber).is_err() {
+            eprintln!("Warn: Logging: a global logger is already installed");
+        }
+        if let Some(e) = file_error {
+            tracing::warn!("cannot write log files: {}", e);
+        }
+
+        let l = Logger { recent, filter: handle };
+        *logger = Some(l.clone());
+        l
+    }
+
+    pub fn set_level(&self, level: LogLevel) {
+        if let Err(e) = self.filter.reload(targets(level)) {
+            tracing::warn!("cannot change the log level: {}", e);
+        }
+    }
+
+    // the newest records, oldest first
+    pub fn recent(&self, limit: usize) -> Vec<LogRecord> {
+        let recent = self.recent.lock().unwrap_or_else(|e| e.into_inner());
+        recent.iter().skip(recent.len().saturating_sub(limit)).cloned().collect()
+    }
+}
+
+fn targets(level: LogLevel) -> Targets {
+    let level = LevelFilter::from_level(level.into());
+    OWN_TARGETS
+        .iter()
+        .fold(Targets::new(), |t, target| t.with_target(*target, level))
+        .with_default(level.min(LevelFilter::WARN))
+}
+
+impl From<LogLevel> for Level {
+    fn from(level: LogLevel) -> Self {
+        match level {
+            LogLevel::Error => Level::ERROR,
+            LogLevel::Warn => Level::WARN,
+            LogLevel::Info => Level::INFO,
+            LogLevel::Debug => Level::DEBUG,
+            LogLevel::Trace => Level::TRACE
+        }
+    }
+}
+
+impl From<&Level> for LogLevel {
+    fn from(level: &Level) -> Self {
+        match *level {
+            Level::ERROR => LogLevel::Error,
+            Level::WARN => LogLevel::Warn,
+            Level::INFO => LogLevel::Info,
+            Level::DEBUG => LogLevel::Debug,
+            _ => LogLevel::Trace
+        }
+    }
+}
+
+// keeps the newest records in memory so they can be attached to bug reports
+struct RecentLayer {
+    recent: Arc<Mutex<VecDeque<LogRecord>>>
+}
+
+// a span's fields formatted once when it is created
+struct SpanFields(String);
+
+impl<S> Layer<S> for RecentLayer where S: Subscriber + for<'a> LookupSpan<'a> {
+
+    fn on_new_span(&self, attrs: &span::Attributes<'_>, id: &span::Id, ctx: Context<'_, S>) {
+        let mut fields = FieldWriter::default();
+        attrs.record(&mut fields);
+        if let Some(span) = ctx.span(id) {
+            span.extensions_mut().insert(SpanFields(fields.fields));
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let mut fields = FieldWriter::default();
+        event.record(&mut fields);
+
+        let spans = ctx.event_scope(event)
+            .map(|scope| scope.from_root().map(|span| {
+                match span.extensions().get::<SpanFields>() {
+                    Some(SpanFields(f)) if !f.is_empty() => format!("{}{{{}}}", span.name(), f),
+                    _ => span.name().to_string()
+                }
+            }).collect())
+            .unwrap_or_default();
+
+        let mut message = fields.message;
+        if !fields.fields.is_empty() {
+            message = format!("{} {}", message, fields.fields);
+        }
+        let record = LogRecord {
+            timestamp: Utc::now(),
+            level: event.metadata().level().into(),
+            target: event.metadata().target().to_string(),
+            spans,
+            message
+        };
+
+        let mut recent = self.recent.lock().unwrap_or_else(|e| e.into_inner());
+        if recent.len() >= RECENT_LIMIT {
+            recent.pop_front();
+        }
+        recent.push_back(record);
+    }
+}
+
+// collects the message and key=value pairs of a span or an event
+#[derive(Default)]
+struct FieldWriter {
+    message: String,
+    fields: String
+}
+
+impl Visit for FieldWriter {
+    // strings are written without quotes
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.record_debug(field, &format_args!("{}", value))
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            self.message = format!("{:?}", value);
+            return
+        }
+        if !self.fields.is_empty() {
+            self.fields.push(' ');
+        }
+        write!(self.fields, "{}={:?}", field.name(), value).unwrap_or(());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::state::LogLevel;
+    use super::Logger;
+
+    #[test]
+    fn logger_keeps_recent_records_with_spans() {
+        let data_dir = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+        let logger = Logger::init(data_dir.to_str().unwrap(), LogLevel::Info);
+
+        let marker = uuid::Uuid::new_v4().to_string();
+        tracing::info_span!("command", key = "SaveReminders").in_scope(|| {
+            tracing::info!(reminder = 3, "saving {}", marker);
+        });
+
+        let record = logger.recent(1000)
+            .into_iter()
+            .find(|r| r.message.contains(&marker))
+            .expect("record was not kept");
+        std::fs::remove_dir_all(&data_dir).unwrap_or(());
+        assert_eq!(record.level, LogLevel::Info);
+        assert_eq!(record.target, "pazcore::logging::tests");
+        assert_eq!(record.spans, vec!["command{key=SaveReminders}".to_string()]);
+        assert_eq!(record.message, format!("saving {} reminder=3", marker));
+    }
+}
@@ -0,0 +1,162 @@
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use ts_rs::TS;
+
+use crate::{duration::format_duration, random::SharedRng, state::{ReminderState, Rotation}};
+
+pub const DEFAULT_TITLE: &str = "{name}";
+pub const DEFAULT_BODY: &str = "Take a {duration} break";
+// the body when the reminder has messages but no body of its own
+pub const DEFAULT_MESSAGE_BODY: &str = "{message}";
+
+// the text of a single occurrence, the same on every surface
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, TS)]
+#[ts(export)]
+pub struct ReminderMessage {
+    pub title: String,
+    pub body: String
+}
+
+// the values a template can refer to, e.g. {count_today}
+pub struct MessageVars<'a> {
+    pub name: &'a str,
+    pub duration_ms: i32,
+    // including the occurrence being rendered
+    pub count_today: usize,
+    pub time: DateTime<Local>,
+    pub message: &'a str
+}
+
+impl MessageVars<'_> {
+    fn get(&self, key: &str) -> Option<String> {
+        Some(match key {
+            "name" => self.name.to_string(),
+            "duration" => format_duration(i64::from(self.duration_ms)),
+            "count_today" => self.count_today.to_string(),
+            "time" => self.time.format("%H:%M").to_string(),
+            "message" => self.message.to_string(),
+            _ => return None
+        })
+    }
+}
+
+// replace every {variable}, unknown ones are kept as written and values are
+// not expanded again, so a message may contain braces
+pub fn render(template: &str, vars: &MessageVars) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        out.push_str(&rest[..open]);
+        let after = &rest[open + 1..];
+        let value = after.find('}').and_then(|close| vars.get(&after[..close]).map(|v| (v, close)));
+        match value {
+            Some((v, close)) => {
+                out.push_str(&v);
+                rest = &after[close + 1..];
+            },
+            None => {
+                out.push('{');
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+// picks each reminder's rotating message, the rotation restarts with the core
+pub struct Messages {
+    // reminder id -> index of the message shown last
+    last: HashMap<String, usize>,
+    rng: SharedRng
+}
+
+impl Messages {
+
+    pub fn new(rng: SharedRng) -> Messages {
+        Messages { last: HashMap::new(), rng }
+    }
+
+    pub fn render(&mut self, reminder: &ReminderState, count_today: usize, time: DateTime<Local>) -> ReminderMessage {
+        let template = &reminder.message;
+        let message = self.pick(reminder).unwrap_or_default();
+        let vars = MessageVars {
+            name: &reminder.name,
+            duration_ms: reminder.duration_ms,
+            count_today,
+            time,
+            message
+        };
+        let default_body = if template.messages.is_empty() { DEFAULT_BODY } else { DEFAULT_MESSAGE_BODY };
+        ReminderMessage {
+            title: render(template.title.as_deref().unwrap_or(DEFAULT_TITLE), &vars),
+            body: render(template.body.as_deref().unwrap_or(default_body), &vars)
+        }
+    }
+
+    fn pick<'a>(&mut self, reminder: &'a ReminderState) -> Option<&'a str> {
+        let messages = &reminder.message.messages;
+        if messages.is_empty() {
+            return None
+        }
+        let last = self.last.get(&reminder.id).copied();
+        let index = match (reminder.message.rotation, last) {
+            (Rotation::RoundRobin, Some(last)) => (last + 1) % messages.len(),
+            (Rotation::RoundRobin, None) => 0,
+            (Rotation::Random, Some(last)) if messages.len() > 1 => {
+                // skip over the last one
+                let i = self.rng.gen_range(0..messages.len() - 1);
+                if i >= last { i + 1 } else { i }
+            },
+            (Rotation::Random, _) => self.rng.gen_range(0..messages.len())
+        };
+        self.last.insert(reminder.id.clone(), index);
+        messages.get(index).map(String::as_str)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Local, NaiveDate, TimeZone};
+    use crate::{random::SharedRng, state::{ReminderState, Rotation}};
+    use super::{render, MessageVars, Messages};
+
+    #[test]
+    fn templates_render_variables() {
+        let vars = MessageVars {
+            name: "Stretch",
+            duration_ms: 90 * 1000,
+            count_today: 3,
+            time: Local.from_local_datetime(&NaiveDate::from_ymd_opt(2024, 1, 1).unwrap().and_hms_opt(14, 5, 0).unwrap()).unwrap(),
+            message: "Roll your {shoulders}"
+        };
+        assert_eq!(render("{name} #{count_today} at {time}", &vars), "Stretch #3 at 14:05");
+        assert_eq!(render("Take a {duration} break", &vars), "Take a 1m30s break");
+        // unknown variables and the message's own braces are left alone
+        assert_eq!(render("{message} {unknown} {", &vars), "Roll your {shoulders} {unknown} {");
+    }
+
+    #[test]
+    fn messages_rotate() {
+        let mut reminder = ReminderState::new("Stretch".to_string(), 60 * 1000, 30 * 1000);
+        let mut messages = Messages::new(SharedRng::seeded(7));
+        let now = Local::now();
+
+        let plain = messages.render(&reminder, 1, now);
+        assert_eq!((plain.title.as_str(), plain.body.as_str()), ("Stretch", "Take a 30s break"));
+
+        reminder.message.messages = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+        reminder.message.rotation = Rotation::RoundRobin;
+        let bodies: Vec<String> = (0..4).map(|_| messages.render(&reminder, 1, now).body).collect();
+        assert_eq!(bodies, ["a", "b", "c", "a"]);
+
+        reminder.message.rotation = Rotation::Random;
+        let mut previous = messages.render(&reminder, 1, now).body;
+        for _ in 0..20 {
+            let body = messages.render(&reminder, 1, now).body;
+            assert_ne!(body, previous);
+            previous = body;
+        }
+    }
+}
@@ -0,0 +1,335 @@
+use serde::{Deserialize, Serialize};
+use tokio::{sync::mpsc::{UnboundedSender, UnboundedReceiver, unbounded_channel}, task::JoinHandle};
+use tracing::warn;
+use ts_rs::TS;
+
+use crate::{ClientCommand, CoreContext};
+
+// how long a reminder is pushed back by the snooze action
+pub const SNOOZE_MS: i32 = 5 * 60 * 1000;
+const SHUTDOWN_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(2);
+
+// the buttons shown on a reminder notification
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, TS)]
+#[ts(export)]
+pub enum NotificationAction {
+    Done,
+    Snooze,
+    Skip
+}
+
+impl NotificationAction {
+    pub const ALL: [NotificationAction; 3] = [NotificationAction::Done, NotificationAction::Snooze, NotificationAction::Skip];
+
+    pub fn key(&self) -> &'static str {
+        match self {
+            NotificationAction::Done => "done",
+            NotificationAction::Snooze => "snooze",
+            NotificationAction::Skip => "skip"
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            NotificationAction::Done => "Done",
+            NotificationAction::Snooze => "Snooze 5 min",
+            NotificationAction::Skip => "Skip"
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<NotificationAction> {
+        NotificationAction::ALL.iter().find(|a| a.key() == key).copied()
+    }
+
+    // the command the core runs when the user clicks the action
+    pub fn command(&self, id: String) -> ClientCommand {
+        match self {
+            NotificationAction::Done => ClientCommand::CompleteReminder { id },
+            NotificationAction::Snooze => ClientCommand::DelayReminder { id, delay: SNOOZE_MS },
+            NotificationAction::Skip => ClientCommand::SkipReminder { id }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum NotifierRequest {
+    // a timeout of 0 keeps the notification until it is dismissed
+    Show { reminder_id: String, title: String, body: String, timeout_ms: i32, sound: bool },
+    Close { reminder_id: String },
+    // close everything still shown and stop serving requests
+    CloseAll
+}
+
+// sends desktop notifications for reminders, actions clicked by the user are
+// sent back to the core as internal events
+pub struct Notifier {
+    tx: UnboundedSender<NotifierRequest>,
+    rx: Option<UnboundedReceiver<NotifierRequest>>,
+    task: Option<JoinHandle<()>>
+}
+
+impl Notifier {
+
+    pub fn new() -> Notifier {
+        let (tx, rx) = unbounded_channel();
+        Notifier {
+            tx,
+            rx: Some(rx),
+            task: None
+        }
+    }
+
+    // connect to the session bus in the background
+    pub fn start(&mut self, ctx: CoreContext) {
+        if let Some(rx) = self.rx.take() {
+            self.task = Some(tokio::spawn(async move {
+                #[cfg(target_os = "linux")]
+                match zbus::Connection::session().await {
+                    Ok(conn) => {
+                        if let Err(e) = dbus::run(conn, rx, ctx.intenal_sender).await {
+                            tracing::error!("notifications stopped: {:?}", e);
+                        }
+                    },
+                    Err(e) => warn!("no session bus, notifications are disabled: {:?}", e)
+                }
+                #[cfg(not(target_os = "linux"))]
+                {
+                    let (_rx, _ctx) = (rx, ctx);
+                    warn!("desktop notifications are not supported on this platform");
+                }
+            }));
+        }
+    }
+
+    pub fn show(&self, reminder_id: &str, title: &str, body: &str, timeout_ms: i32, sound: bool) {
+        self.tx.send(NotifierRequest::Show {
+            reminder_id: reminder_id.to_string(),
+            title: title.to_string(),
+            body: body.to_string(),
+            timeout_ms,
+            sound
+        }).unwrap_or(());
+    }
+
+    pub fn close(&self, reminder_id: &str) {
+        self.tx.send(NotifierRequest::Close { reminder_id: reminder_id.to_string() }).unwrap_or(());
+    }
+
+    // close every open notification, waiting a moment for the bus to catch up
+    pub async fn shutdown(&mut self) {
+        self.tx.send(NotifierRequest::CloseAll).unwrap_or(());
+        if let Some(task) = self.task.take() {
+            if tokio::time::timeout(SHUTDOWN_TIMEOUT, task).await.is_err() {
+                warn!("gave up closing notifications");
+            }
+        }
+    }
+}
+
+impl Default for Notifier {
+    fn default() -> Self {
+        Notifier::new()
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub mod dbus {
+    use std::collections::HashMap;
+    use futures_util::StreamExt;
+    use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
+    use tracing::{error, warn};
+    use zbus::{dbus_proxy, zvariant::Value, Connection};
+
+    use crate::InternalEvent;
+    use super::{NotificationAction, NotifierRequest};
+
+    static APP_NAME: &str = "Paz";
+
+    #[dbus_proxy(
+        interface = "org.freedesktop.Notifications",
+        default_service = "org.freedesktop.Notifications",
+        default_path = "/org/freedesktop/Notifications"
+    )]
+    trait Notifications {
+        #[allow(clippy::too_many_arguments)]
+        fn notify(
+            &self,
+            app_name: &str,
+            replaces_id: u32,
+            app_icon: &str,
+            summary: &str,
+            body: &str,
+            actions: &[&str],
+            hints: HashMap<&str, Value<'_>>,
+            expire_timeout: i32,
+        ) -> zbus::Result<u32>;
+
+        fn close_notification(&self, id: u32) -> zbus::Result<()>;
+
+        #[dbus_proxy(signal)]
+        fn action_invoked(&self, id: u32, action_key: &str) -> zbus::Result<()>;
+
+        #[dbus_proxy(signal)]
+        fn notification_closed(&self, id: u32, reason: u32) -> zbus::Result<()>;
+    }
+
+    // serve notification requests until the core goes away
+    pub async fn run(conn: Connection, mut rx: UnboundedReceiver<NotifierRequest>, sender: UnboundedSender<InternalEvent>) -> zbus::Result<()> {
+        let proxy = NotificationsProxy::new(&conn).await?;
+        let mut actions = proxy.receive_action_invoked().await?;
+        let mut closed = proxy.receive_notification_closed().await?;
+
+        // notification id -> reminder id
+        let mut shown = HashMap::<u32, String>::new();
+
+        let action_keys: Vec<&str> = NotificationAction::ALL
+            .iter()
+            .flat_map(|a| [a.key(), a.label()])
+            .collect();
+
+        loop {
+            tokio::select! {
+                req = rx.recv() => match req {
+                    Some(NotifierRequest::Show { reminder_id, title, body, timeout_ms, sound }) => {
+                        // replace the previous notification of the same reminder
+                        let replaces = take_notification(&mut shown, &reminder_id).unwrap_or(0);
+                        let mut hints = HashMap::new();
+                        if !sound {
+                            hints.insert("suppress-sound", Value::from(true));
+                        }
+                        match proxy.notify(APP_NAME, replaces, "", &title, &body, &action_keys, hints, timeout_ms).await {
+                            Ok(id) => { shown.insert(id, reminder_id); },
+                            Err(e) => error!("cannot show notification: {:?}", e)
+                        }
+                    },
+                    Some(NotifierRequest::Close { reminder_id }) => {
+                        if let Some(id) = take_notification(&mut shown, &reminder_id) {
+                            proxy.close_notification(id).await.unwrap_or(());
+                        }
+                    },
+                    Some(NotifierRequest::CloseAll) => {
+                        for id in shown.keys() {
+                            proxy.close_notification(*id).await.unwrap_or(());
+                        }
+                        return Ok(())
+                    },
+                    None => return Ok(())
+                },
+                Some(signal) = actions.next() => {
+                    // a malformed signal from some other sender must not end the notifier
+                    let args = match signal.args() {
+                        Ok(args) => args,
+                        Err(e) => {
+                            warn!("ignoring a notification action: {:?}", e);
+                            continue
+                        }
+                    };
+                    if let (Some(reminder_id), Some(action)) = (shown.get(&args.id), NotificationAction::from_key(args.action_key)) {
+                        sender.send(InternalEvent::NotificationAction { reminder_id: reminder_id.clone(), action }).unwrap_or(());
+                    }
+                },
+                Some(signal) = closed.next() => {
+                    match signal.args() {
+                        Ok(args) => { shown.remove(&args.id); },
+                        Err(e) => warn!("ignoring a closed notification: {:?}", e)
+                    }
+                }
+            }
+        }
+    }
+
+    fn take_notification(shown: &mut HashMap<u32, String>, reminder_id: &str) -> Option<u32> {
+        let id = shown.iter().find(|(_, r)| *r == reminder_id).map(|(id, _)| *id)?;
+        shown.remove(&id);
+        Some(id)
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use std::{collections::HashMap, io::{BufRead, BufReader}, process::{Child, Command, Stdio}, sync::{Arc, Mutex}};
+        use tokio::sync::mpsc::unbounded_channel;
+        use zbus::{dbus_interface, zvariant::Value, ConnectionBuilder, SignalContext};
+
+        use crate::{InternalEvent, notification::{NotificationAction, NotifierRequest}};
+
+        // summary and actions of every notification shown
+        type Received = Arc<Mutex<Vec<(String, Vec<String>)>>>;
+
+        // stand-in for the desktop's notification daemon
+        struct FakeNotifications {
+            received: Received
+        }
+
+        #[dbus_interface(name = "org.freedesktop.Notifications")]
+        impl FakeNotifications {
+            #[allow(clippy::too_many_arguments)]
+            fn notify(&self, _app_name: &str, _replaces_id: u32, _app_icon: &str, summary: &str, _body: &str, actions: Vec<String>, _hints: HashMap<String, Value<'_>>, _expire_timeout: i32) -> u32 {
+                let mut received = self.received.lock().unwrap();
+                received.push((summary.to_string(), actions));
+                received.len() as u32
+            }
+
+            fn close_notification(&self, _id: u32) {}
+
+            #[dbus_interface(signal)]
+            async fn action_invoked(ctxt: &SignalContext<'_>, id: u32, action_key: &str) -> zbus::Result<()>;
+        }
+
+        // start a private session bus, returning the daemon and its address
+        fn start_private_bus() -> Option<(Child, String)> {
+            let mut daemon = Command::new("dbus-daemon")
+                .args(["--session", "--nofork", "--print-address"])
+                .stdout(Stdio::piped())
+                .spawn()
+                .ok()?;
+            let mut address = String::new();
+            BufReader::new(daemon.stdout.take()?).read_line(&mut address).ok()?;
+            Some((daemon, address.trim().to_string()))
+        }
+
+        #[tokio::test]
+        #[ignore = "needs dbus-daemon, run with cargo test -- --ignored"]
+        async fn notification_action_maps_to_internal_event() {
+            let (mut daemon, address) = start_private_bus().expect("dbus-daemon is not available");
+
+            let received = Arc::new(Mutex::new(Vec::new()));
+            let server = ConnectionBuilder::address(address.as_str()).unwrap()
+                .name("org.freedesktop.Notifications").unwrap()
+                .serve_at("/org/freedesktop/Notifications", FakeNotifications { received: received.clone() }).unwrap()
+                .build()
+                .await
+                .unwrap();
+            let client = ConnectionBuilder::address(address.as_str()).unwrap().build().await.unwrap();
+
+            let (req_tx, req_rx) = unbounded_channel();
+            let (event_tx, mut event_rx) = unbounded_channel();
+            tokio::spawn(super::run(client, req_rx, event_tx));
+
+            req_tx.send(NotifierRequest::Show {
+                reminder_id: "r1".to_string(),
+                title: "Stretch".to_string(),
+                body: "".to_string(),
+                timeout_ms: 1000,
+                sound: true
+            }).unwrap();
+
+            // wait for the notification to reach the fake daemon
+            for _ in 0..50 {
+                if !received.lock().unwrap().is_empty() {
+                    break;
+                }
+                tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+            }
+            let (summary, actions) = received.lock().unwrap()[0].clone();
+            assert_eq!(summary, "Stretch");
+            assert_eq!(actions, vec!["done", "Done", "snooze", "Snooze 5 min", "skip", "Skip"]);
+
+            let ctxt = SignalContext::new(&server, "/org/freedesktop/Notifications").unwrap();
+            FakeNotifications::action_invoked(&ctxt, 1, "snooze").await.unwrap();
+
+            let event = tokio::time::timeout(std::time::Duration::from_secs(5), event_rx.recv()).await.unwrap();
+            daemon.kill().unwrap_or(());
+            assert_eq!(event.unwrap(), InternalEvent::NotificationAction { reminder_id: "r1".to_string(), action: NotificationAction::Snooze });
+        }
+    }
+}
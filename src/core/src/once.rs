@@ -0,0 +1,94 @@
+use chrono::{DateTime, Duration, LocalResult, NaiveDate, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+use crate::{profiles::parse_time, state::ReminderState, CoreError};
+
+// a one-shot reminder is set at most this far ahead
+pub const MAX_AHEAD_DAYS: i64 = 366;
+
+// when a one-shot reminder fires, as the user put it
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
+#[serde(tag = "key", content = "value")]
+#[ts(export)]
+pub enum OnceTarget {
+  // a wall clock time as HH:MM, today unless a date is given as YYYY-MM-DD
+  At { time: String, date: Option<String> },
+  // a countdown from now
+  In { delay_ms: i32 }
+}
+
+impl ReminderState {
+    // the instant a one-shot reminder still has to fire at
+    pub fn pending_once(&self) -> Option<DateTime<Utc>> {
+        self.once.as_ref().filter(|o| !o.completed).map(|o| o.at)
+    }
+}
+
+fn invalid(field: &str, reason: &str) -> CoreError {
+    CoreError::Validation { field: format!("target.{}", field), reason: reason.to_string() }
+}
+
+// the instant to fire at, a wall clock time is read in the timezone of now,
+// so 16:00 stays 16:00 local time across daylight saving changes
+pub fn resolve<Tz: TimeZone>(target: &OnceTarget, now: &DateTime<Tz>) -> Result<DateTime<Utc>, CoreError> {
+    let now_utc = now.with_timezone(&Utc);
+    let at = match target {
+        OnceTarget::In { delay_ms } => {
+            if *delay_ms <= 0 {
+                return Err(invalid("delay_ms", "must be positive"))
+            }
+            now_utc + Duration::milliseconds(i64::from(*delay_ms))
+        },
+        OnceTarget::At { time, date } => {
+            let time = parse_time(time).ok_or_else(|| invalid("time", "must be a time as HH:MM"))?;
+            let date = match date {
+                Some(d) => NaiveDate::parse_from_str(d.trim(), "%Y-%m-%d").map_err(|_| invalid("date", "must be a date as YYYY-MM-DD"))?,
+                None => now.date_naive()
+            };
+            match now.timezone().from_local_datetime(&date.and_time(time)) {
+                LocalResult::Single(at) => at.with_timezone(&Utc),
+                // a time repeated when the clocks go back fires the first time round
+                LocalResult::Ambiguous(earliest, _) => earliest.with_timezone(&Utc),
+                LocalResult::None => return Err(invalid("time", "does not exist, the clocks go forward then"))
+            }
+        }
+    };
+    if at <= now_utc {
+        return Err(invalid("time", "is in the past"))
+    }
+    if at - now_utc > Duration::days(MAX_AHEAD_DAYS) {
+        return Err(invalid("date", &format!("must be within {} days", MAX_AHEAD_DAYS)))
+    }
+    Ok(at)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{FixedOffset, NaiveDate, TimeZone, Utc};
+    use crate::CoreError;
+    use super::{resolve, OnceTarget};
+
+    #[test]
+    fn targets_resolve_in_the_given_timezone() {
+        let berlin = FixedOffset::east_opt(2 * 60 * 60).unwrap();
+        let now = berlin.from_local_datetime(&NaiveDate::from_ymd_opt(2024, 6, 3).unwrap().and_hms_opt(14, 30, 0).unwrap()).unwrap();
+        let at = |time: &str, date: Option<&str>| resolve(&OnceTarget::At { time: time.to_string(), date: date.map(|d| d.to_string()) }, &now);
+
+        // 16:00 in berlin is 14:00 utc
+        assert_eq!(at("16:00", None).unwrap(), Utc.from_utc_datetime(&NaiveDate::from_ymd_opt(2024, 6, 3).unwrap().and_hms_opt(14, 0, 0).unwrap()));
+        assert_eq!(at("08:15", Some("2024-06-04")).unwrap(), Utc.from_utc_datetime(&NaiveDate::from_ymd_opt(2024, 6, 4).unwrap().and_hms_opt(6, 15, 0).unwrap()));
+        let countdown = resolve(&OnceTarget::In { delay_ms: 90 * 60 * 1000 }, &now).unwrap();
+        assert_eq!((countdown - now.with_timezone(&Utc)).num_minutes(), 90);
+
+        let field = |res: Result<_, CoreError>| match res {
+            Err(CoreError::Validation { field, .. }) => field,
+            other => panic!("expected a validation error, got {:?}", other)
+        };
+        assert_eq!(field(at("14:00", None)), "target.time");
+        assert_eq!(field(at("25:00", None)), "target.time");
+        assert_eq!(field(at("16:00", Some("tomorrow"))), "target.date");
+        assert_eq!(field(at("16:00", Some("2026-01-01"))), "target.date");
+        assert_eq!(field(resolve(&OnceTarget::In { delay_ms: 0 }, &now)), "target.delay_ms");
+    }
+}
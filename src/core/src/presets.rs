@@ -0,0 +1,148 @@
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+use crate::{exercises::ExerciseSelection, state::{MessageTemplate, ReminderKind, ReminderState, Rotation}};
+
+// what a new state starts with until onboarding is finished, turned off so
+// nothing interrupts before the user picked
+pub const SUGGESTED_PRESETS: [&str; 2] = ["eye-20-20-20", "hydration"];
+
+const MINUTE_MS: i32 = 60 * 1000;
+
+// a ready made reminder offered during onboarding and in the reminder list
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
+#[ts(export)]
+pub struct Preset {
+  pub id: String,
+  pub name: String,
+  pub description: String,
+  pub wait_ms: i32,
+  pub duration_ms: i32,
+  pub message: MessageTemplate,
+  pub exercises: Option<ExerciseSelection>,
+  pub kind: ReminderKind
+}
+
+impl Preset {
+    // a new, active reminder with its own id
+    pub fn reminder(&self) -> ReminderState {
+        let mut reminder = ReminderState::new(self.name.clone(), self.wait_ms, self.duration_ms);
+        reminder.is_active = true;
+        reminder.message = self.message.clone();
+        reminder.exercises = self.exercises.clone();
+        reminder.kind = self.kind.clone();
+        reminder
+    }
+}
+
+fn template(title: &str, body: &str, messages: &[&str]) -> MessageTemplate {
+    MessageTemplate {
+        title: Some(title.to_string()),
+        body: Some(body.to_string()),
+        messages: messages.iter().map(|m| m.to_string()).collect(),
+        rotation: Rotation::Random
+    }
+}
+
+pub fn catalogue() -> Vec<Preset> {
+    vec![
+        Preset {
+            id: "eye-20-20-20".to_string(),
+            name: "20-20-20 eye rule".to_string(),
+            description: "Every 20 minutes, look at something 20 feet (6 m) away for 20 seconds".to_string(),
+            wait_ms: 20 * MINUTE_MS,
+            duration_ms: 20 * 1000,
+            message: template("Eye break", "Look at something 20 feet (6 m) away for {duration}", &[]),
+            exercises: None,
+            kind: ReminderKind::Break
+        },
+        Preset {
+            id: "hydration".to_string(),
+            name: "Hydration".to_string(),
+            description: "A glass of water every hour".to_string(),
+            wait_ms: 60 * MINUTE_MS,
+            duration_ms: 15 * 1000,
+            message: template("Drink some water", "{message}", &[
+                "Time for a glass of water",
+                "Refill your bottle while you are at it",
+                "A sip now beats a headache later"
+            ]),
+            exercises: None,
+            kind: ReminderKind::Hydration { amount_ml: 250 }
+        },
+        Preset {
+            id: "stand-up".to_string(),
+            name: "Hourly stand-up".to_string(),
+            description: "Get up and move for two minutes every hour".to_string(),
+            wait_ms: 60 * MINUTE_MS,
+            duration_ms: 2 * MINUTE_MS,
+            message: template("Stand up", "{message}", &[
+                "Stand up and move for {duration}",
+                "Walk around for a bit, the work will wait",
+                "Stretch your legs, this is stand-up number {count_today} today"
+            ]),
+            exercises: Some(ExerciseSelection::Set("standing".to_string())),
+            kind: ReminderKind::Break
+        },
+        Preset {
+            id: "posture".to_string(),
+            name: "Posture check".to_string(),
+            description: "A short reminder to sit up straight every half hour".to_string(),
+            wait_ms: 30 * MINUTE_MS,
+            duration_ms: 10 * 1000,
+            message: template("Posture check", "{message}", &[
+                "Shoulders back and down",
+                "Feet flat on the floor, back against the chair",
+                "Is the top of your screen at eye level?",
+                "Unclench your jaw and relax your shoulders"
+            ]),
+            exercises: None,
+            kind: ReminderKind::Break
+        },
+        Preset {
+            id: "desk-stretch".to_string(),
+            name: "Desk stretch".to_string(),
+            description: "A minute of seated stretches every 45 minutes".to_string(),
+            wait_ms: 45 * MINUTE_MS,
+            duration_ms: MINUTE_MS,
+            message: template("Stretch", "Take {duration} to stretch", &[]),
+            exercises: Some(ExerciseSelection::Set("desk".to_string())),
+            kind: ReminderKind::Break
+        }
+    ]
+}
+
+pub fn find(id: &str) -> Option<Preset> {
+    catalogue().into_iter().find(|p| p.id == id)
+}
+
+// the suggested presets, turned off
+pub fn suggested() -> Vec<ReminderState> {
+    SUGGESTED_PRESETS
+        .iter()
+        .filter_map(|id| find(id))
+        .map(|p| ReminderState { is_active: false, ..p.reminder() })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use crate::validation::validate_reminders;
+    use super::{catalogue, find, suggested, SUGGESTED_PRESETS};
+
+    #[test]
+    fn presets_are_valid() {
+        let presets = catalogue();
+        let ids: HashSet<&str> = presets.iter().map(|p| p.id.as_str()).collect();
+        assert_eq!(ids.len(), presets.len());
+        // every preset can be applied next to every other one
+        let reminders: Vec<_> = presets.iter().map(|p| p.reminder()).collect();
+        assert!(validate_reminders(&reminders).is_ok());
+        assert!(reminders.iter().all(|r| r.is_active));
+
+        assert_eq!(suggested().len(), SUGGESTED_PRESETS.len());
+        assert!(suggested().iter().all(|r| !r.is_active));
+        assert!(find("missing").is_none());
+    }
+}
@@ -0,0 +1,174 @@
+use chrono::{Datelike, NaiveDateTime, NaiveTime};
+use std::time::Duration;
+use tokio::time::interval;
+
+use crate::{state::{ClientState, ProfileRule, ProfileState, Weekday, WorkingHours}, CoreContext, CoreError, InternalEvent};
+
+// rules are given to the minute, checking more often gains nothing
+pub const RULE_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+impl From<Weekday> for chrono::Weekday {
+    fn from(day: Weekday) -> Self {
+        match day {
+            Weekday::Mon => chrono::Weekday::Mon,
+            Weekday::Tue => chrono::Weekday::Tue,
+            Weekday::Wed => chrono::Weekday::Wed,
+            Weekday::Thu => chrono::Weekday::Thu,
+            Weekday::Fri => chrono::Weekday::Fri,
+            Weekday::Sat => chrono::Weekday::Sat,
+            Weekday::Sun => chrono::Weekday::Sun
+        }
+    }
+}
+
+// times in rules are written as HH:MM
+pub fn parse_time(time: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(time.trim(), "%H:%M").ok()
+}
+
+impl ProfileRule {
+    // a rule reaching past midnight counts for the weekday it started on,
+    // e.g. fri 22:00 to 06:00 still applies early on saturday
+    pub fn applies(&self, now: NaiveDateTime) -> bool {
+        // a time which does not parse keeps the rule from ever applying
+        let (start, end) = match (self.start.as_deref().map(parse_time), self.end.as_deref().map(parse_time)) {
+            (Some(None), _) | (_, Some(None)) => return false,
+            (start, end) => (start.flatten(), end.flatten())
+        };
+
+        let time = now.time();
+        let after_start = !matches!(start, Some(s) if time < s);
+        let day = match (start, end) {
+            (_, None) if after_start => now.weekday(),
+            (Some(s), Some(e)) if e <= s && time < e => now.weekday().pred(),
+            (Some(s), Some(e)) if e <= s && after_start => now.weekday(),
+            (_, Some(e)) if after_start && time < e => now.weekday(),
+            _ => return false
+        };
+        self.weekdays.is_empty() || self.weekdays.iter().any(|d| chrono::Weekday::from(*d) == day)
+    }
+}
+
+impl WorkingHours {
+    // always true while working hours are turned off
+    pub fn contains(&self, now: NaiveDateTime) -> bool {
+        let rule = ProfileRule {
+            weekdays: self.weekdays.clone(),
+            start: Some(self.start.clone()),
+            end: Some(self.end.clone())
+        };
+        !self.enabled || rule.applies(now)
+    }
+}
+
+// the first profile with a rule which applies, in the order the profiles are listed
+pub fn matching_profile(profiles: &[ProfileState], now: NaiveDateTime) -> Option<&ProfileState> {
+    profiles.iter().find(|p| p.rules.iter().any(|r| r.applies(now)))
+}
+
+// store the active reminders and settings with their profile and bring in the
+// ones of the profile switched to
+pub fn switch(state: &mut ClientState, id: &str) -> Result<(), CoreError> {
+    let target = state.profiles
+        .iter()
+        .position(|p| p.id == id)
+        .ok_or_else(|| CoreError::profile_not_found(id))?;
+    if state.active_profile == id {
+        return Ok(())
+    }
+
+    let active = state.active_profile.clone();
+    if let Some(current) = state.profiles.iter_mut().find(|p| p.id == active) {
+        current.reminders = std::mem::take(&mut state.reminders);
+        current.notifications = state.notifications;
+        current.paused = state.paused;
+    }
+    let target = &mut state.profiles[target];
+    state.reminders = std::mem::take(&mut target.reminders);
+    state.notifications = target.notifications;
+    state.paused = target.paused;
+    state.active_profile = id.to_string();
+    Ok(())
+}
+
+// ask the core to check the switching rules now and every minute after
+pub fn start_rule_timer(ctx: CoreContext) {
+    tokio::spawn(async move {
+        let mut timer = interval(RULE_CHECK_INTERVAL);
+        loop {
+            timer.tick().await;
+            if ctx.intenal_sender.send(InternalEvent::ProfileRulesDue).is_err() {
+                break
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+    use crate::state::{ClientState, ProfileRule, ProfileState, Weekday};
+    use super::{matching_profile, switch};
+
+    fn at(day: u32, time: &str) -> chrono::NaiveDateTime {
+        // 2024-01-01 was a monday
+        NaiveDate::from_ymd_opt(2024, 1, day).unwrap().and_time(super::parse_time(time).unwrap())
+    }
+
+    #[test]
+    fn rules_apply_by_weekday_and_time() {
+        let office = ProfileRule {
+            weekdays: vec![Weekday::Mon, Weekday::Tue, Weekday::Wed, Weekday::Thu, Weekday::Fri],
+            start: Some("09:00".to_string()),
+            end: Some("17:30".to_string())
+        };
+        assert!(office.applies(at(1, "09:00")));
+        assert!(!office.applies(at(1, "17:30")));
+        assert!(!office.applies(at(6, "12:00")));
+
+        let night = ProfileRule { weekdays: vec![Weekday::Fri], start: Some("22:00".to_string()), end: Some("06:00".to_string()) };
+        assert!(night.applies(at(5, "23:00")));
+        assert!(night.applies(at(6, "05:59")));
+        assert!(!night.applies(at(5, "05:59")));
+
+        let weekend = ProfileRule { weekdays: vec![Weekday::Sat, Weekday::Sun], ..ProfileRule::default() };
+        assert!(weekend.applies(at(7, "00:00")));
+        assert!(!weekend.applies(at(1, "12:00")));
+
+        let broken = ProfileRule { start: Some("9am".to_string()), ..ProfileRule::default() };
+        assert!(!broken.applies(at(1, "12:00")));
+
+        let mut work = ProfileState::new("Work".to_string());
+        work.rules.push(office);
+        let mut home = ProfileState::new("Home".to_string());
+        home.rules.push(weekend);
+        let profiles = vec![work, home];
+        assert_eq!(matching_profile(&profiles, at(2, "10:00")).map(|p| p.name.as_str()), Some("Work"));
+        assert_eq!(matching_profile(&profiles, at(6, "10:00")).map(|p| p.name.as_str()), Some("Home"));
+        assert!(matching_profile(&profiles, at(2, "20:00")).is_none());
+    }
+
+    #[test]
+    fn switch_swaps_reminders_and_settings() {
+        let mut state = ClientState::new("");
+        let default_id = state.active_profile.clone();
+        let reminders: Vec<String> = state.reminders.iter().map(|r| r.id.clone()).collect();
+        let focus = ProfileState::new("Focus".to_string());
+        let focus_id = focus.id.clone();
+        state.profiles.push(focus);
+
+        switch(&mut state, &focus_id).unwrap();
+        state.notifications = false;
+        assert_eq!(state.active_profile, focus_id);
+        assert!(state.reminders.is_empty());
+        assert_eq!(state.profiles[0].reminders.len(), reminders.len());
+
+        switch(&mut state, &default_id).unwrap();
+        assert!(state.notifications);
+        assert_eq!(state.reminders.iter().map(|r| r.id.clone()).collect::<Vec<String>>(), reminders);
+        assert!(state.profiles[0].reminders.is_empty());
+        assert!(!state.profiles[1].notifications);
+
+        assert_eq!(switch(&mut state, "missing").unwrap_err().code(), "not_found");
+    }
+}
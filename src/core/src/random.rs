@@ -0,0 +1,26 @@
+use rand::{distributions::uniform::{SampleRange, SampleUniform}, rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use std::sync::{Arc, Mutex, PoisonError};
+
+// the core's one source of randomness, shared by timers, messages and
+// exercises, tests seed it so every pick can be repeated
+#[derive(Clone)]
+pub struct SharedRng(Arc<Mutex<StdRng>>);
+
+impl SharedRng {
+
+    pub fn from_entropy() -> SharedRng {
+        SharedRng(Arc::new(Mutex::new(StdRng::from_entropy())))
+    }
+
+    pub fn seeded(seed: u64) -> SharedRng {
+        SharedRng(Arc::new(Mutex::new(StdRng::seed_from_u64(seed))))
+    }
+
+    pub fn gen_range<T: SampleUniform, R: SampleRange<T>>(&self, range: R) -> T {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner).gen_range(range)
+    }
+
+    pub fn shuffle<T>(&self, items: &mut [T]) {
+        items.shuffle(&mut *self.0.lock().unwrap_or_else(PoisonError::into_inner))
+    }
+}
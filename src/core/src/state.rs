@@ -1,9 +1,12 @@
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 use ts_rs::TS;
 use std::fs;
 use std::io::{BufReader, Write, Error};
 use std::result::Result;
 use uuid::Uuid;
+use chrono::{DateTime, Utc};
+
+use crate::{exercises::ExerciseSelection, presets};
 
 pub static CLIENT_STATE_CONFIG_NAME: &str = "client_state.json";
 
@@ -12,7 +15,36 @@ pub static CLIENT_STATE_CONFIG_NAME: &str = "client_state.json";
 pub struct ClientState {
     pub client_uuid: String,
     pub data_path: String,
-    pub reminders: Vec<ReminderState>
+    pub reminders: Vec<ReminderState>,
+    #[serde(default)]
+    pub webhooks: Vec<WebhookState>,
+    #[serde(default)]
+    pub hooks: HookState,
+    // hooks run arbitrary commands, so they stay off until the user opts in
+    #[serde(default)]
+    pub allow_command_hooks: bool,
+    #[serde(default = "default_hook_timeout_ms")]
+    pub hook_timeout_ms: i32,
+    #[serde(default = "default_notifications")]
+    pub notifications: bool,
+    #[serde(default)]
+    pub paused: bool,
+    #[serde(default)]
+    pub http_api: HttpApiState,
+    #[serde(default)]
+    pub log_level: LogLevel,
+    // every profile including the active one, whose reminders and settings
+    // are the ones above
+    #[serde(default)]
+    pub profiles: Vec<ProfileState>,
+    #[serde(default)]
+    pub active_profile: String,
+    #[serde(default)]
+    pub settings: Settings,
+    // false until the first run picked its reminders, states written before
+    // onboarding existed count as onboarded
+    #[serde(default = "default_true")]
+    pub onboarded: bool
 }
 
 impl ClientState {
@@ -22,22 +54,39 @@ impl ClientState {
             client_uuid: Uuid::new_v4().to_string(),
             data_path: data_path.to_string(),
             reminders: Vec::new(),
+            webhooks: Vec::new(),
+            hooks: HookState::default(),
+            allow_command_hooks: false,
+            hook_timeout_ms: default_hook_timeout_ms(),
+            notifications: default_notifications(),
+            paused: false,
+            http_api: HttpApiState::default(),
+            log_level: LogLevel::default(),
+            profiles: Vec::new(),
+            active_profile: String::new(),
+            settings: Settings::default(),
+            onboarded: false
         };
+        config.ensure_profile();
 
-        config.reminders.push(ReminderState::new("Stretch".to_string(), 10 * 1000, 15 * 1000));
-        config.reminders.push(ReminderState::new("Drink Water".to_string(), 20 * 1000, 30 * 1000));
+        config.reminders = presets::suggested();
         return config;
     }
 
-    pub fn save(&self) {
-        let content = &self.clone();
+    pub fn save(&self) -> Result<(), Error> {
         // only write to disk if config path is set
-        if !content.data_path.is_empty() {
-          let config_path = format!("{}/{}", content.data_path, CLIENT_STATE_CONFIG_NAME);
-          let mut file = fs::File::create(config_path).unwrap();
-          let json = serde_json::to_string(content).unwrap();
-          file.write_all(json.as_bytes()).unwrap();
+        if !self.data_path.is_empty() {
+          let config_path = format!("{}/{}", self.data_path, CLIENT_STATE_CONFIG_NAME);
+          // written next to the state and renamed over it, so the watcher and
+          // other readers never see a half written file
+          let tmp_path = format!("{}.tmp", config_path);
+          let mut file = fs::File::create(&tmp_path)?;
+          let json = serde_json::to_string(self)?;
+          file.write_all(json.as_bytes())?;
+          file.sync_all()?;
+          fs::rename(tmp_path, config_path)?;
         }
+        Ok(())
       }
     
       pub fn read_disk(&mut self) -> Result<(),Error> {
@@ -48,8 +97,22 @@ impl ClientState {
         let data = serde_json::from_reader(reader)?;
         // assign to self
         *self = data;
+        self.ensure_profile();
         Ok(())
       }
+
+    // states written before profiles existed get a single profile holding their reminders
+    pub fn ensure_profile(&mut self) {
+        if !self.profiles.iter().any(|p| p.id == self.active_profile) {
+            let profile = ProfileState::new("Default".to_string());
+            self.active_profile = profile.id.clone();
+            self.profiles.insert(0, profile);
+        }
+    }
+
+    pub fn active_profile(&self) -> Option<&ProfileState> {
+        self.profiles.iter().find(|p| p.id == self.active_profile)
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, Default, TS)]
@@ -59,7 +122,25 @@ pub struct ReminderState {
   pub name: String,
   pub is_active: bool,
   pub wait_ms: i32,
-  pub duration_ms: i32
+  pub duration_ms: i32,
+  #[serde(default)]
+  pub hooks: HookState,
+  #[serde(default)]
+  pub message: MessageTemplate,
+  // guidance shown during the break, none for a plain reminder
+  #[serde(default)]
+  pub exercises: Option<ExerciseSelection>,
+  #[serde(default)]
+  pub kind: ReminderKind,
+  // lets responses move the interval, none keeps it fixed
+  #[serde(default)]
+  pub adaptive: Option<AdaptiveInterval>,
+  // how each wait is drawn from the interval
+  #[serde(default)]
+  pub timing: ReminderTiming,
+  // fires a single time instead of on the interval, see once.rs
+  #[serde(default)]
+  pub once: Option<OneShot>
 }
 
 impl ReminderState {
@@ -69,14 +150,503 @@ impl ReminderState {
         name: name,
         is_active: false,
         wait_ms: wait,
-        duration_ms: duration
+        duration_ms: duration,
+        hooks: HookState::default(),
+        message: MessageTemplate::default(),
+        exercises: None,
+        kind: ReminderKind::default(),
+        adaptive: None,
+        timing: ReminderTiming::default(),
+        once: None
+      }
+    }
+}
+
+// a partial update of a reminder, fields left out keep their current value
+#[derive(Debug, Serialize, Deserialize, Clone, Default, TS)]
+#[ts(export)]
+pub struct ReminderPatch {
+  #[serde(default)]
+  pub name: Option<String>,
+  #[serde(default)]
+  pub is_active: Option<bool>,
+  #[serde(default)]
+  pub wait_ms: Option<i32>,
+  #[serde(default)]
+  pub duration_ms: Option<i32>,
+  // no hooks, their commands run in a shell and are only changed in the app
+  #[serde(default)]
+  pub message: Option<MessageTemplate>,
+  // null clears the exercises, left out keeps them
+  #[serde(default, deserialize_with = "double_option")]
+  pub exercises: Option<Option<ExerciseSelection>>,
+  #[serde(default)]
+  pub kind: Option<ReminderKind>,
+  // null makes the interval fixed again
+  #[serde(default, deserialize_with = "double_option")]
+  pub adaptive: Option<Option<AdaptiveInterval>>,
+  #[serde(default)]
+  pub timing: Option<ReminderTiming>,
+  // null puts a one-shot reminder back on its interval
+  #[serde(default, deserialize_with = "double_option")]
+  pub once: Option<Option<OneShot>>
+}
+
+// tells a field set to null apart from one left out
+fn double_option<'de, T: Deserialize<'de>, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Option<T>>, D::Error> {
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+impl ReminderPatch {
+    pub fn apply(self, reminder: &mut ReminderState) {
+      if let Some(name) = self.name {
+        reminder.name = name;
+      }
+      if let Some(is_active) = self.is_active {
+        reminder.is_active = is_active;
+      }
+      if let Some(wait_ms) = self.wait_ms {
+        reminder.wait_ms = wait_ms;
+      }
+      if let Some(duration_ms) = self.duration_ms {
+        reminder.duration_ms = duration_ms;
+      }
+      if let Some(message) = self.message {
+        reminder.message = message;
+      }
+      if let Some(exercises) = self.exercises {
+        reminder.exercises = exercises;
+      }
+      if let Some(kind) = self.kind {
+        reminder.kind = kind;
+      }
+      if let Some(adaptive) = self.adaptive {
+        // new bounds keep the interval in use unless one is given
+        let current = reminder.adaptive.as_ref().and_then(|a| a.current_wait_ms);
+        reminder.adaptive = adaptive.map(|a| AdaptiveInterval { current_wait_ms: a.current_wait_ms.or(current), ..a });
+      }
+      if let Some(timing) = self.timing {
+        reminder.timing = timing;
+      }
+      if let Some(once) = self.once {
+        reminder.once = once;
+      }
+    }
+}
+
+// a named set of reminders and settings, e.g. work and home
+#[derive(Debug, Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct ProfileState {
+  pub id: String,
+  pub name: String,
+  // empty for the active profile, its reminders live in the client state
+  #[serde(default)]
+  pub reminders: Vec<ReminderState>,
+  #[serde(default = "default_notifications")]
+  pub notifications: bool,
+  #[serde(default)]
+  pub paused: bool,
+  // the profile is switched to when one of these starts to apply
+  #[serde(default)]
+  pub rules: Vec<ProfileRule>
+}
+
+impl ProfileState {
+    pub fn new(name: String) -> ProfileState {
+      ProfileState {
+        id: Uuid::new_v4().to_string(),
+        name,
+        reminders: Vec::new(),
+        notifications: default_notifications(),
+        paused: false,
+        rules: Vec::new()
+      }
+    }
+}
+
+// applies on the given weekdays between start and end, local time as HH:MM,
+// an end before the start reaches past midnight
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, TS)]
+#[ts(export)]
+pub struct ProfileRule {
+  // every day when empty
+  #[serde(default)]
+  pub weekdays: Vec<Weekday>,
+  // the whole day when left out
+  #[serde(default)]
+  pub start: Option<String>,
+  #[serde(default)]
+  pub end: Option<String>
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, TS)]
+#[serde(rename_all = "lowercase")]
+#[ts(export)]
+pub enum Weekday {
+  Mon,
+  Tue,
+  Wed,
+  Thu,
+  Fri,
+  Sat,
+  Sun
+}
+
+// a partial update of a profile, fields left out keep their current value
+#[derive(Debug, Serialize, Deserialize, Clone, Default, TS)]
+#[ts(export)]
+pub struct ProfilePatch {
+  #[serde(default)]
+  pub name: Option<String>,
+  #[serde(default)]
+  pub rules: Option<Vec<ProfileRule>>
+}
+
+// what a reminder says when it starts, e.g. a title of "{name}" and a body of
+// "Take a {duration} break", see messages.rs for the variables
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, TS)]
+#[ts(export)]
+pub struct MessageTemplate {
+  // the defaults are used when left out
+  #[serde(default)]
+  pub title: Option<String>,
+  #[serde(default)]
+  pub body: Option<String>,
+  // one of these is picked per occurrence and available as {message}
+  #[serde(default)]
+  pub messages: Vec<String>,
+  #[serde(default)]
+  pub rotation: Rotation
+}
+
+// the bounds responses may move a reminder's interval within, see adaptive.rs
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, TS)]
+#[ts(export)]
+pub struct AdaptiveInterval {
+  pub min_wait_ms: i32,
+  pub max_wait_ms: i32,
+  // the interval in use, none until responses moved it away from wait_ms
+  #[serde(default)]
+  pub current_wait_ms: Option<i32>
+}
+
+// a reminder firing once at an instant, turned off and completed when it does
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, TS)]
+#[ts(export)]
+pub struct OneShot {
+  pub at: DateTime<Utc>,
+  // removed once its break ended instead of being kept as completed
+  #[serde(default)]
+  pub delete_when_done: bool,
+  #[serde(default)]
+  pub completed: bool
+}
+
+// how each wait is drawn, a fixed interval is easy to tune out
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, TS)]
+#[serde(tag = "key", content = "value")]
+#[ts(export)]
+pub enum ReminderTiming {
+  #[default]
+  Fixed,
+  // the interval moved by up to this much either way
+  Jitter { jitter_ms: i32 },
+  // anywhere between the two, the interval itself is not used
+  Window { min_wait_ms: i32, max_wait_ms: i32 }
+}
+
+// what answering the reminder means
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, TS)]
+#[serde(tag = "key", content = "value")]
+#[ts(export)]
+pub enum ReminderKind {
+  #[default]
+  Break,
+  // completing the reminder logs the amount drunk, see hydration.rs
+  Hydration { amount_ml: i32 }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export)]
+pub enum Rotation {
+  // never the same message twice in a row
+  #[default]
+  Random,
+  RoundRobin
+}
+
+// shell commands run when a reminder starts or ends
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default, TS)]
+#[ts(export)]
+pub struct HookState {
+  pub on_start: Option<String>,
+  pub on_end: Option<String>
+}
+
+fn default_hook_timeout_ms() -> i32 {
+    30 * 1000
+}
+
+fn default_notifications() -> bool {
+    true
+}
+
+// an outgoing webhook which receives a signed POST for every core event
+#[derive(Debug, Serialize, Deserialize, Clone, Default, TS)]
+#[ts(export)]
+pub struct WebhookState {
+  pub id: String,
+  pub url: String,
+  pub secret: String,
+  pub is_active: bool
+}
+
+impl WebhookState {
+    pub fn new(url: String, secret: String) -> WebhookState {
+      WebhookState {
+        id: Uuid::new_v4().to_string(),
+        url,
+        secret,
+        is_active: true
+      }
+    }
+}
+
+// the optional localhost http api, every request must carry the token as a bearer token
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, TS)]
+#[ts(export)]
+pub struct HttpApiState {
+  pub enabled: bool,
+  pub port: u16,
+  pub token: String
+}
+
+impl Default for HttpApiState {
+    fn default() -> Self {
+      HttpApiState {
+        enabled: false,
+        port: 7420,
+        token: Uuid::new_v4().simple().to_string()
       }
     }
 }
 
+// how much the core logs, to stdout and the log files in the data directory
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, TS)]
+#[serde(rename_all = "lowercase")]
+#[ts(export)]
+pub enum LogLevel {
+  Error,
+  Warn,
+  #[default]
+  Info,
+  Debug,
+  Trace
+}
+
+// app wide preferences, unlike reminders they are shared by every profile
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, TS)]
+#[ts(export)]
+pub struct Settings {
+  #[serde(default)]
+  pub new_reminder: NewReminderDefaults,
+  #[serde(default)]
+  pub notification: NotificationSettings,
+  #[serde(default)]
+  pub startup: StartupSettings,
+  #[serde(default)]
+  pub working_hours: WorkingHours,
+  // a language tag such as en or de-AT, used to format times and dates
+  #[serde(default = "default_locale")]
+  pub locale: String,
+  #[serde(default)]
+  pub integrations: IntegrationSettings,
+  #[serde(default)]
+  pub hydration: HydrationSettings
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+      Settings {
+        new_reminder: NewReminderDefaults::default(),
+        notification: NotificationSettings::default(),
+        startup: StartupSettings::default(),
+        working_hours: WorkingHours::default(),
+        locale: default_locale(),
+        integrations: IntegrationSettings::default(),
+        hydration: HydrationSettings::default()
+      }
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_locale() -> String {
+    "en".to_string()
+}
+
+// what a reminder created without further details starts with
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, TS)]
+#[ts(export)]
+pub struct NewReminderDefaults {
+  pub wait_ms: i32,
+  pub duration_ms: i32,
+  pub is_active: bool
+}
+
+impl Default for NewReminderDefaults {
+    fn default() -> Self {
+      NewReminderDefaults {
+        wait_ms: 45 * 60 * 1000,
+        duration_ms: 30 * 1000,
+        is_active: true
+      }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, TS)]
+#[ts(export)]
+pub struct NotificationSettings {
+  #[serde(default)]
+  pub style: NotificationStyle,
+  #[serde(default = "default_true")]
+  pub sound: bool
+}
+
+impl Default for NotificationSettings {
+    fn default() -> Self {
+      NotificationSettings {
+        style: NotificationStyle::default(),
+        sound: true
+      }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, TS)]
+#[serde(rename_all = "lowercase")]
+#[ts(export)]
+pub enum NotificationStyle {
+  // closes by itself once the break is over
+  #[default]
+  Banner,
+  // stays until it is dismissed or acted on
+  Persistent
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, TS)]
+#[ts(export)]
+pub struct StartupSettings {
+  // only the tray icon is shown on launch
+  #[serde(default)]
+  pub start_minimized: bool,
+  #[serde(default)]
+  pub start_paused: bool
+}
+
+// outside of working hours reminders keep their schedule but stay quiet,
+// neither notifications nor hooks are run
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, TS)]
+#[ts(export)]
+pub struct WorkingHours {
+  pub enabled: bool,
+  pub weekdays: Vec<Weekday>,
+  // local time as HH:MM
+  pub start: String,
+  pub end: String
+}
+
+impl Default for WorkingHours {
+    fn default() -> Self {
+      WorkingHours {
+        enabled: false,
+        weekdays: vec![Weekday::Mon, Weekday::Tue, Weekday::Wed, Weekday::Thu, Weekday::Fri],
+        start: "09:00".to_string(),
+        end: "17:00".to_string()
+      }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, TS)]
+#[ts(export)]
+pub struct IntegrationSettings {
+  // turns every configured webhook off without losing them
+  #[serde(default = "default_true")]
+  pub webhooks: bool
+}
+
+impl Default for IntegrationSettings {
+    fn default() -> Self {
+      IntegrationSettings { webhooks: true }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, TS)]
+#[ts(export)]
+pub struct HydrationSettings {
+  pub daily_goal_ml: i32,
+  // hydration reminders come sooner while behind the goal and stay quiet once it is met
+  #[serde(default = "default_true")]
+  pub adaptive: bool
+}
+
+impl Default for HydrationSettings {
+    fn default() -> Self {
+      HydrationSettings { daily_goal_ml: 2000, adaptive: true }
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, TS)]
 #[ts(export)]
 pub enum View {
   Dashboard,
   Settings
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+    use super::{AdaptiveInterval, ReminderPatch, ReminderState};
+
+    fn patched(patch: &str, reminder: &ReminderState) -> ReminderState {
+        let mut reminder = reminder.clone();
+        serde_json::from_str::<ReminderPatch>(patch).unwrap().apply(&mut reminder);
+        reminder
+    }
+
+    fn field(reminder: &ReminderState, name: &str) -> Value {
+        serde_json::to_value(reminder).unwrap()[name].clone()
+    }
+
+    #[test]
+    fn patch_sets_keeps_and_clears_fields() {
+        let mut reminder = ReminderState::new("Stretch".to_string(), 40 * 60 * 1000, 10 * 1000);
+        reminder.adaptive = Some(AdaptiveInterval { min_wait_ms: 1000, max_wait_ms: 2000, current_wait_ms: Some(1500) });
+
+        // field, patch setting it, value it ends up with, patch clearing it, value after clearing
+        let cases = [
+            ("exercises", r#"{"exercises": {"key": "Set", "value": "desk"}}"#,
+                json!({"key": "Set", "value": "desk"}), r#"{"exercises": null}"#, Value::Null),
+            ("kind", r#"{"kind": {"key": "Hydration", "value": {"amount_ml": 300}}}"#,
+                json!({"key": "Hydration", "value": {"amount_ml": 300}}), r#"{"kind": {"key": "Break"}}"#, json!({"key": "Break"})),
+            // the interval in use survives moving the bounds
+            ("adaptive", r#"{"adaptive": {"min_wait_ms": 1000, "max_wait_ms": 3000}}"#,
+                json!({"min_wait_ms": 1000, "max_wait_ms": 3000, "current_wait_ms": 1500}), r#"{"adaptive": null}"#, Value::Null),
+            ("timing", r#"{"timing": {"key": "Jitter", "value": {"jitter_ms": 60000}}}"#,
+                json!({"key": "Jitter", "value": {"jitter_ms": 60000}}), r#"{"timing": {"key": "Fixed"}}"#, json!({"key": "Fixed"})),
+            ("once", r#"{"once": {"at": "2030-05-01T14:00:00Z"}}"#,
+                json!({"at": "2030-05-01T14:00:00Z", "delete_when_done": false, "completed": false}), r#"{"once": null}"#, Value::Null),
+        ];
+
+        for (name, set, expected, clear, cleared) in cases {
+            let with = patched(set, &reminder);
+            assert_eq!(field(&with, name), expected, "{}", name);
+            // left out keeps it
+            assert_eq!(field(&patched(r#"{"name": "Desk stretch"}"#, &with), name), expected, "{}", name);
+            assert_eq!(field(&patched(clear, &with), name), cleared, "{}", name);
+        }
+    }
+}
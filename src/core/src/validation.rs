@@ -0,0 +1,372 @@
+use std::collections::HashSet;
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::{CoreError, exercises::Library, profiles, state::{ProfileState, ReminderKind, ReminderState, ReminderTiming, Settings}};
+
+// reminders fire at most once a second and at least once a week
+pub const MIN_WAIT_MS: i32 = 1000;
+pub const MAX_WAIT_MS: i32 = 7 * 24 * 60 * 60 * 1000;
+// a break lasts at most a day
+pub const MAX_DURATION_MS: i32 = 24 * 60 * 60 * 1000;
+pub const MAX_NAME_LEN: usize = 100;
+// message templates and each rotating message
+pub const MAX_MESSAGE_LEN: usize = 500;
+pub const MAX_MESSAGES: usize = 100;
+// a single drink and a day's water
+pub const MAX_INTAKE_ML: i32 = 5000;
+pub const MAX_DAILY_GOAL_ML: i32 = 20000;
+
+fn invalid(field: &str, reason: &str) -> CoreError {
+    CoreError::Validation { field: field.to_string(), reason: reason.to_string() }
+}
+
+// check a single reminder, its name must not be taken by any of the others
+pub fn validate_reminder(reminder: &ReminderState, others: &[ReminderState]) -> Result<(), CoreError> {
+    if Uuid::parse_str(&reminder.id).is_err() {
+        return Err(invalid("id", "must be a uuid"))
+    }
+
+    let name = reminder.name.trim();
+    if name.is_empty() {
+        return Err(invalid("name", "must not be empty"))
+    }
+    if name.chars().count() > MAX_NAME_LEN {
+        return Err(invalid("name", &format!("must be at most {} characters", MAX_NAME_LEN)))
+    }
+    if others.iter().any(|r| r.id != reminder.id && r.name.trim().eq_ignore_ascii_case(name)) {
+        return Err(invalid("name", "is already used by another reminder"))
+    }
+
+    if reminder.wait_ms < MIN_WAIT_MS {
+        return Err(invalid("wait_ms", &format!("must be at least {} ms", MIN_WAIT_MS)))
+    }
+    if reminder.wait_ms > MAX_WAIT_MS {
+        return Err(invalid("wait_ms", &format!("must be at most {} ms", MAX_WAIT_MS)))
+    }
+    if reminder.duration_ms <= 0 {
+        return Err(invalid("duration_ms", "must be positive"))
+    }
+    if reminder.duration_ms > MAX_DURATION_MS {
+        return Err(invalid("duration_ms", &format!("must be at most {} ms", MAX_DURATION_MS)))
+    }
+
+    if let Some(selection) = &reminder.exercises {
+        if !Library::bundled().contains(selection) {
+            return Err(invalid("exercises", "no such exercise set"))
+        }
+    }
+
+    if let Some(adaptive) = &reminder.adaptive {
+        if adaptive.min_wait_ms < MIN_WAIT_MS {
+            return Err(invalid("adaptive.min_wait_ms", &format!("must be at least {} ms", MIN_WAIT_MS)))
+        }
+        if adaptive.max_wait_ms < adaptive.min_wait_ms || adaptive.max_wait_ms > MAX_WAIT_MS {
+            return Err(invalid("adaptive.max_wait_ms", &format!("must be between the minimum and {} ms", MAX_WAIT_MS)))
+        }
+    }
+
+    match reminder.timing {
+        ReminderTiming::Fixed => {},
+        ReminderTiming::Jitter { jitter_ms } => {
+            // the earliest wait must still be positive, also once an adaptive
+            // interval moved down to its minimum
+            let shortest = match &reminder.adaptive {
+                Some(a) => reminder.wait_ms.min(a.min_wait_ms),
+                None => reminder.wait_ms
+            };
+            if jitter_ms <= 0 || jitter_ms >= shortest {
+                return Err(invalid("timing.jitter_ms", "must be positive and less than the shortest interval"))
+            }
+        },
+        ReminderTiming::Window { min_wait_ms, max_wait_ms } => {
+            if min_wait_ms < MIN_WAIT_MS {
+                return Err(invalid("timing.min_wait_ms", &format!("must be at least {} ms", MIN_WAIT_MS)))
+            }
+            if max_wait_ms < min_wait_ms || max_wait_ms > MAX_WAIT_MS {
+                return Err(invalid("timing.max_wait_ms", &format!("must be between the minimum and {} ms", MAX_WAIT_MS)))
+            }
+        }
+    }
+
+    if reminder.is_active && matches!(&reminder.once, Some(o) if o.completed) {
+        return Err(invalid("is_active", "a completed one-shot reminder cannot be turned on again, snooze it instead"))
+    }
+
+    if let ReminderKind::Hydration { amount_ml } = reminder.kind {
+        validate_intake(amount_ml).map_err(|_| invalid("kind.amount_ml", &format!("must be between 1 and {} ml", MAX_INTAKE_ML)))?;
+    }
+
+    let message = &reminder.message;
+    for (field, template) in [("message.title", &message.title), ("message.body", &message.body)] {
+        if template.as_ref().map_or(0, |t| t.chars().count()) > MAX_MESSAGE_LEN {
+            return Err(invalid(field, &format!("must be at most {} characters", MAX_MESSAGE_LEN)))
+        }
+    }
+    if message.messages.len() > MAX_MESSAGES {
+        return Err(invalid("message.messages", &format!("must hold at most {} messages", MAX_MESSAGES)))
+    }
+    for (i, m) in message.messages.iter().enumerate() {
+        if m.trim().is_empty() || m.chars().count() > MAX_MESSAGE_LEN {
+            return Err(invalid(&format!("message.messages[{}]", i), &format!("must be between 1 and {} characters", MAX_MESSAGE_LEN)))
+        }
+    }
+    Ok(())
+}
+
+// check every reminder of a list, errors point at the reminder's index e.g. reminders[2].name
+pub fn validate_reminders(reminders: &[ReminderState]) -> Result<(), CoreError> {
+    let mut ids = HashSet::new();
+    for (i, reminder) in reminders.iter().enumerate() {
+        if !ids.insert(reminder.id.as_str()) {
+            return Err(invalid(&format!("reminders[{}].id", i), "is used by more than one reminder"))
+        }
+        // a duplicate name is reported on the later reminder
+        validate_reminder(reminder, &reminders[..i]).map_err(|e| match e {
+            CoreError::Validation { field, reason } => CoreError::Validation { field: format!("reminders[{}].{}", i, field), reason },
+            other => other
+        })?;
+    }
+    Ok(())
+}
+
+pub fn validate_delay(delay: i32) -> Result<(), CoreError> {
+    if delay <= 0 {
+        return Err(invalid("delay", "must be positive"))
+    }
+    if delay > MAX_WAIT_MS {
+        return Err(invalid("delay", &format!("must be at most {} ms", MAX_WAIT_MS)))
+    }
+    Ok(())
+}
+
+// check a profile's name and rules, its name must not be taken by any of the others
+pub fn validate_profile(profile: &ProfileState, others: &[ProfileState]) -> Result<(), CoreError> {
+    let name = profile.name.trim();
+    if name.is_empty() {
+        return Err(invalid("name", "must not be empty"))
+    }
+    if name.chars().count() > MAX_NAME_LEN {
+        return Err(invalid("name", &format!("must be at most {} characters", MAX_NAME_LEN)))
+    }
+    if others.iter().any(|p| p.id != profile.id && p.name.trim().eq_ignore_ascii_case(name)) {
+        return Err(invalid("name", "is already used by another profile"))
+    }
+
+    for (i, rule) in profile.rules.iter().enumerate() {
+        for (field, time) in [("start", &rule.start), ("end", &rule.end)] {
+            if matches!(time.as_deref().map(profiles::parse_time), Some(None)) {
+                return Err(invalid(&format!("rules[{}].{}", i, field), "must be a time such as 09:30"))
+            }
+        }
+    }
+    Ok(())
+}
+
+// check every profile of a list, errors point at the profile's index e.g. profiles[1].name
+pub fn validate_profiles(profiles: &[ProfileState]) -> Result<(), CoreError> {
+    let mut ids = HashSet::new();
+    for (i, profile) in profiles.iter().enumerate() {
+        if !ids.insert(profile.id.as_str()) {
+            return Err(invalid(&format!("profiles[{}].id", i), "is used by more than one profile"))
+        }
+        validate_profile(profile, &profiles[..i]).map_err(|e| match e {
+            CoreError::Validation { field, reason } => CoreError::Validation { field: format!("profiles[{}].{}", i, field), reason },
+            other => other
+        })?;
+        validate_reminders(&profile.reminders).map_err(|e| match e {
+            CoreError::Validation { field, reason } => CoreError::Validation { field: format!("profiles[{}].{}", i, field), reason },
+            other => other
+        })?;
+    }
+    Ok(())
+}
+
+pub fn validate_settings(settings: &Settings) -> Result<(), CoreError> {
+    let defaults = &settings.new_reminder;
+    if defaults.wait_ms < MIN_WAIT_MS || defaults.wait_ms > MAX_WAIT_MS {
+        return Err(invalid("new_reminder.wait_ms", &format!("must be between {} and {} ms", MIN_WAIT_MS, MAX_WAIT_MS)))
+    }
+    if defaults.duration_ms <= 0 || defaults.duration_ms > MAX_DURATION_MS {
+        return Err(invalid("new_reminder.duration_ms", &format!("must be between 1 and {} ms", MAX_DURATION_MS)))
+    }
+
+    let hours = &settings.working_hours;
+    for (field, time) in [("working_hours.start", &hours.start), ("working_hours.end", &hours.end)] {
+        if profiles::parse_time(time).is_none() {
+            return Err(invalid(field, "must be a time such as 09:30"))
+        }
+    }
+    if hours.enabled && hours.weekdays.is_empty() {
+        return Err(invalid("working_hours.weekdays", "must contain at least one day"))
+    }
+
+    // e.g. en, de-AT or zh-Hant-TW
+    let locale_ok = settings.locale
+        .split('-')
+        .all(|part| (1..=8).contains(&part.len()) && part.chars().all(|c| c.is_ascii_alphanumeric()));
+    if !locale_ok {
+        return Err(invalid("locale", "must be a language tag such as en or de-AT"))
+    }
+
+    let goal = settings.hydration.daily_goal_ml;
+    if goal <= 0 || goal > MAX_DAILY_GOAL_ML {
+        return Err(invalid("hydration.daily_goal_ml", &format!("must be between 1 and {} ml", MAX_DAILY_GOAL_ML)))
+    }
+    Ok(())
+}
+
+pub fn validate_intake(amount_ml: i32) -> Result<(), CoreError> {
+    if amount_ml <= 0 || amount_ml > MAX_INTAKE_ML {
+        return Err(invalid("amount_ml", &format!("must be between 1 and {} ml", MAX_INTAKE_ML)))
+    }
+    Ok(())
+}
+
+// a hand edited state file must not stop the core from starting, reminders which
+// fail validation are switched off until they are fixed
+pub fn deactivate_invalid(reminders: &mut [ReminderState]) {
+    let snapshot = reminders.to_vec();
+    for (i, reminder) in reminders.iter_mut().enumerate() {
+        if let Err(e) = validate_reminder(reminder, &snapshot[..i]) {
+            warn!("reminder {} ({}): {}", reminder.id, reminder.name, e);
+            reminder.is_active = false;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{CoreError, state::{AdaptiveInterval, OneShot, ProfileRule, ProfileState, ReminderKind, ReminderState, ReminderTiming, Settings}};
+    use super::{deactivate_invalid, validate_delay, validate_intake, validate_profile, validate_profiles, validate_reminders, validate_settings, MAX_INTAKE_ML, MAX_WAIT_MS};
+
+    fn field_of(res: Result<(), CoreError>) -> String {
+        match res {
+            Err(CoreError::Validation { field, .. }) => field,
+            other => panic!("expected a validation error but got {:?}", other)
+        }
+    }
+
+    #[test]
+    fn validation_rejects_bad_fields() {
+        let valid = ReminderState::new("Stretch".to_string(), 60 * 1000, 10 * 1000);
+        assert!(validate_reminders(std::slice::from_ref(&valid)).is_ok());
+
+        let mut r = valid.clone();
+        r.wait_ms = 0;
+        assert_eq!(field_of(validate_reminders(&[r])), "reminders[0].wait_ms");
+
+        let mut r = valid.clone();
+        r.wait_ms = MAX_WAIT_MS + 1;
+        assert_eq!(field_of(validate_reminders(&[r])), "reminders[0].wait_ms");
+
+        let mut r = valid.clone();
+        r.duration_ms = -5;
+        assert_eq!(field_of(validate_reminders(&[r])), "reminders[0].duration_ms");
+
+        let mut r = valid.clone();
+        r.name = "  ".to_string();
+        assert_eq!(field_of(validate_reminders(&[r])), "reminders[0].name");
+
+        let mut r = valid.clone();
+        r.id = "not-a-uuid".to_string();
+        assert_eq!(field_of(validate_reminders(&[r])), "reminders[0].id");
+
+        let mut r = valid.clone();
+        r.message.messages = vec!["Roll your shoulders".to_string(), " ".to_string()];
+        assert_eq!(field_of(validate_reminders(&[r])), "reminders[0].message.messages[1]");
+
+        let mut r = valid.clone();
+        r.kind = ReminderKind::Hydration { amount_ml: 0 };
+        assert_eq!(field_of(validate_reminders(&[r])), "reminders[0].kind.amount_ml");
+        assert_eq!(field_of(validate_intake(MAX_INTAKE_ML + 1)), "amount_ml");
+
+        let mut r = valid.clone();
+        r.adaptive = Some(AdaptiveInterval { min_wait_ms: 60 * 1000, max_wait_ms: 30 * 1000, current_wait_ms: None });
+        assert_eq!(field_of(validate_reminders(&[r])), "reminders[0].adaptive.max_wait_ms");
+
+        let mut r = valid.clone();
+        r.timing = ReminderTiming::Jitter { jitter_ms: r.wait_ms };
+        assert_eq!(field_of(validate_reminders(&[r])), "reminders[0].timing.jitter_ms");
+        // 40m give or take 35m is fine until the interval adapts down to 5m
+        let mut r = valid.clone();
+        r.wait_ms = 40 * 60 * 1000;
+        r.timing = ReminderTiming::Jitter { jitter_ms: 35 * 60 * 1000 };
+        assert!(validate_reminders(std::slice::from_ref(&r)).is_ok());
+        r.adaptive = Some(AdaptiveInterval { min_wait_ms: 5 * 60 * 1000, max_wait_ms: 60 * 60 * 1000, current_wait_ms: None });
+        assert_eq!(field_of(validate_reminders(&[r])), "reminders[0].timing.jitter_ms");
+        let mut r = valid.clone();
+        r.timing = ReminderTiming::Window { min_wait_ms: 10, max_wait_ms: 60 * 1000 };
+        assert_eq!(field_of(validate_reminders(&[r])), "reminders[0].timing.min_wait_ms");
+
+        let mut r = valid.clone();
+        r.is_active = true;
+        r.once = Some(OneShot { at: chrono::Utc::now(), delete_when_done: false, completed: true });
+        assert_eq!(field_of(validate_reminders(&[r])), "reminders[0].is_active");
+
+        assert_eq!(field_of(validate_delay(0)), "delay");
+    }
+
+    #[test]
+    fn validation_requires_unique_names_and_ids() {
+        let first = ReminderState::new("Stretch".to_string(), 60 * 1000, 10 * 1000);
+        let same_name = ReminderState::new("stretch ".to_string(), 60 * 1000, 10 * 1000);
+        assert_eq!(field_of(validate_reminders(&[first.clone(), same_name])), "reminders[1].name");
+
+        let mut same_id = ReminderState::new("Walk".to_string(), 60 * 1000, 10 * 1000);
+        same_id.id = first.id.clone();
+        assert_eq!(field_of(validate_reminders(&[first, same_id])), "reminders[1].id");
+    }
+
+    #[test]
+    fn validation_deactivates_invalid_on_load() {
+        let mut valid = ReminderState::new("Stretch".to_string(), 60 * 1000, 10 * 1000);
+        let mut invalid = ReminderState::new("Walk".to_string(), -1, 10 * 1000);
+        valid.is_active = true;
+        invalid.is_active = true;
+
+        let mut reminders = vec![valid, invalid];
+        deactivate_invalid(&mut reminders);
+        assert!(reminders[0].is_active);
+        assert!(!reminders[1].is_active);
+    }
+
+    #[test]
+    fn validation_checks_profiles() {
+        let work = ProfileState::new("Work".to_string());
+        assert!(validate_profile(&work, &[]).is_ok());
+
+        let same_name = ProfileState::new(" work".to_string());
+        assert_eq!(field_of(validate_profile(&same_name, std::slice::from_ref(&work))), "name");
+
+        let mut bad_rule = ProfileState::new("Home".to_string());
+        bad_rule.rules.push(ProfileRule { end: Some("25:00".to_string()), ..ProfileRule::default() });
+        assert_eq!(field_of(validate_profile(&bad_rule, std::slice::from_ref(&work))), "rules[0].end");
+
+        assert!(validate_profiles(std::slice::from_ref(&work)).is_ok());
+        assert_eq!(field_of(validate_profiles(&[work.clone(), same_name])), "profiles[1].name");
+        assert_eq!(field_of(validate_profiles(&[work.clone(), work])), "profiles[1].id");
+    }
+
+    #[test]
+    fn validation_checks_settings() {
+        assert!(validate_settings(&Settings::default()).is_ok());
+
+        let mut settings = Settings::default();
+        settings.new_reminder.wait_ms = 0;
+        assert_eq!(field_of(validate_settings(&settings)), "new_reminder.wait_ms");
+
+        let mut settings = Settings::default();
+        settings.working_hours.start = "9".to_string();
+        assert_eq!(field_of(validate_settings(&settings)), "working_hours.start");
+
+        let settings = Settings { locale: "de-AT".to_string(), ..Settings::default() };
+        assert!(validate_settings(&settings).is_ok());
+
+        let mut settings = Settings::default();
+        settings.hydration.daily_goal_ml = 0;
+        assert_eq!(field_of(validate_settings(&settings)), "hydration.daily_goal_ml");
+        let settings = Settings { locale: "de_AT".to_string(), ..Settings::default() };
+        assert_eq!(field_of(validate_settings(&settings)), "locale");
+    }
+}
@@ -0,0 +1,109 @@
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use serde_json::Value;
+use std::{ffi::OsStr, path::Path, time::Duration};
+use tokio::sync::mpsc::unbounded_channel;
+use tracing::{debug, warn};
+
+use crate::{state::{ClientState, CLIENT_STATE_CONFIG_NAME}, CoreContext, InternalEvent};
+
+// editors save in several steps, wait for them to settle before reading the file
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+// watches the client state file for edits made outside of the app, stops when dropped
+pub struct StateWatcher {
+    _watcher: RecommendedWatcher
+}
+
+// the directory is watched rather than the file, editors and dotfile managers
+// often replace the file which would end a watch on the file itself
+pub fn watch(data_path: &str, ctx: CoreContext) -> Result<StateWatcher, notify::Error> {
+    let (tx, mut rx) = unbounded_channel();
+    let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+        match res {
+            Ok(event) => {
+                let touches_state = event.paths
+                    .iter()
+                    .any(|p| p.file_name() == Some(OsStr::new(CLIENT_STATE_CONFIG_NAME)));
+                if touches_state && !matches!(event.kind, EventKind::Access(_) | EventKind::Remove(_)) {
+                    tx.send(()).unwrap_or(());
+                }
+            },
+            Err(e) => warn!("{:?}", e)
+        }
+    })?;
+    watcher.watch(Path::new(data_path), RecursiveMode::NonRecursive)?;
+
+    tokio::spawn(async move {
+        while rx.recv().await.is_some() {
+            tokio::time::sleep(DEBOUNCE).await;
+            while rx.try_recv().is_ok() {}
+            debug!("{} changed on disk", CLIENT_STATE_CONFIG_NAME);
+            if ctx.intenal_sender.send(InternalEvent::StateFileChanged).is_err() {
+                break
+            }
+        }
+    });
+    Ok(StateWatcher { _watcher: watcher })
+}
+
+// what a reload would change compared to the state in memory
+#[derive(Debug, PartialEq)]
+pub enum StateDiff {
+    // e.g. the core's own writes, which trigger the watcher as well
+    Unchanged,
+    // only this reminder was edited
+    Reminder(String),
+    Other
+}
+
+pub fn diff(current: &ClientState, loaded: &ClientState) -> StateDiff {
+    let (mut a, mut b) = match (serde_json::to_value(current), serde_json::to_value(loaded)) {
+        (Ok(a), Ok(b)) => (a, b),
+        _ => return StateDiff::Other
+    };
+    if a == b {
+        return StateDiff::Unchanged
+    }
+
+    let (ra, rb) = (take_reminders(&mut a), take_reminders(&mut b));
+    let same_reminders = ra.len() == rb.len() && ra.iter().zip(&rb).all(|(x, y)| x["id"] == y["id"]);
+    if a != b || !same_reminders {
+        return StateDiff::Other
+    }
+    let mut edited = ra.iter().zip(&rb).filter(|(x, y)| x != y);
+    match (edited.next(), edited.next()) {
+        (Some((x, _)), None) => StateDiff::Reminder(x["id"].as_str().unwrap_or_default().to_string()),
+        _ => StateDiff::Other
+    }
+}
+
+fn take_reminders(state: &mut Value) -> Vec<Value> {
+    match state.get_mut("reminders").map(Value::take) {
+        Some(Value::Array(r)) => r,
+        _ => Vec::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::state::ClientState;
+    use super::{diff, StateDiff};
+
+    #[test]
+    fn diff_finds_edited_reminder() {
+        let current = ClientState::new("");
+        assert_eq!(diff(&current, &current.clone()), StateDiff::Unchanged);
+
+        let mut renamed = current.clone();
+        renamed.reminders[1].name = "Tea".to_string();
+        assert_eq!(diff(&current, &renamed), StateDiff::Reminder(current.reminders[1].id.clone()));
+
+        let mut removed = current.clone();
+        removed.reminders.pop();
+        assert_eq!(diff(&current, &removed), StateDiff::Other);
+
+        let mut paused = current.clone();
+        paused.paused = true;
+        assert_eq!(diff(&current, &paused), StateDiff::Other);
+    }
+}
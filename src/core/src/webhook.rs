@@ -0,0 +1,497 @@
+use chrono::{DateTime, Utc, Duration};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::fs;
+use std::io::{BufReader, Write, Error};
+use std::sync::{Arc, Mutex, PoisonError};
+use tokio::{sync::Notify, task::JoinHandle};
+use tracing::warn;
+use ts_rs::TS;
+use uuid::Uuid;
+
+use crate::{messages::ReminderMessage, state::WebhookState, CoreEvent};
+
+pub static WEBHOOK_QUEUE_NAME: &str = "webhook_queue.json";
+pub static SIGNATURE_HEADER: &str = "X-Paz-Signature";
+
+// a delivery is marked as failed after this many attempts
+const MAX_ATTEMPTS: u32 = 5;
+// first retry waits this long, doubling on each attempt after
+const RETRY_BASE_MS: i64 = 5 * 1000;
+// number of finished deliveries kept around for the status query
+const HISTORY_LIMIT: usize = 50;
+const REQUEST_TIMEOUT_MS: u64 = 10 * 1000;
+// how long a shutdown waits for due deliveries
+const SHUTDOWN_TIMEOUT_MS: u64 = 5 * 1000;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, TS)]
+#[ts(export)]
+pub struct WebhookPayload {
+  pub reminder_id: String,
+  pub reminder_name: String,
+  pub event: String,
+  pub occurred_at: DateTime<Utc>,
+  pub next_execution: Option<DateTime<Utc>>,
+  // what the reminder said when it started, queued deliveries from older versions have none
+  #[serde(default)]
+  pub message: Option<ReminderMessage>,
+  // the whole event for anything but a reminder starting or ending, e.g. the error of an EngineError
+  #[serde(default)]
+  pub details: Option<CoreEvent>
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, TS)]
+#[ts(export)]
+pub enum DeliveryStatus {
+  Pending,
+  Delivered,
+  Failed
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct WebhookDelivery {
+  pub id: String,
+  pub webhook_id: String,
+  pub url: String,
+  pub payload: WebhookPayload,
+  // the exact bytes which were signed, sent unchanged on every attempt
+  #[serde(default)]
+  pub body: String,
+  pub signature: String,
+  pub status: DeliveryStatus,
+  pub attempts: u32,
+  pub last_error: Option<String>,
+  pub last_attempt: Option<DateTime<Utc>>,
+  pub next_attempt: DateTime<Utc>
+}
+
+impl WebhookDelivery {
+    pub fn new(webhook: &WebhookState, payload: WebhookPayload) -> WebhookDelivery {
+      let body = serde_json::to_string(&payload).unwrap();
+      WebhookDelivery {
+        id: Uuid::new_v4().to_string(),
+        webhook_id: webhook.id.clone(),
+        url: webhook.url.clone(),
+        signature: sign(&webhook.secret, &body),
+        body,
+        payload,
+        status: DeliveryStatus::Pending,
+        attempts: 0,
+        last_error: None,
+        last_attempt: None,
+        next_attempt: Utc::now()
+      }
+    }
+
+    fn record_attempt(&mut self, result: Result<(), String>) {
+      let now = Utc::now();
+      self.attempts += 1;
+      self.last_attempt = Some(now);
+      match result {
+        Ok(()) => {
+          self.status = DeliveryStatus::Delivered;
+          self.last_error = None;
+        },
+        Err(e) => {
+          self.last_error = Some(e);
+          if self.attempts >= MAX_ATTEMPTS {
+            self.status = DeliveryStatus::Failed;
+          } else {
+            let backoff = RETRY_BASE_MS * 2_i64.pow(self.attempts - 1);
+            self.next_attempt = now + Duration::milliseconds(backoff);
+          }
+        }
+      }
+    }
+}
+
+// hex encoded HMAC-SHA256 of the request body, prefixed with the algorithm
+pub fn sign(secret: &str, body: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).unwrap();
+    mac.update(body.as_bytes());
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+// a payload as it was serialized before it had a message
+#[derive(Serialize)]
+struct LegacyPayload<'a> {
+  reminder_id: &'a str,
+  reminder_name: &'a str,
+  event: &'a str,
+  occurred_at: &'a DateTime<Utc>,
+  next_execution: &'a Option<DateTime<Utc>>
+}
+
+fn legacy_body(payload: &WebhookPayload) -> Result<String, serde_json::Error> {
+    serde_json::to_string(&LegacyPayload {
+      reminder_id: &payload.reminder_id,
+      reminder_name: &payload.reminder_name,
+      event: &payload.event,
+      occurred_at: &payload.occurred_at,
+      next_execution: &payload.next_execution
+    })
+}
+
+// the retry queue, persisted next to the client state so pending deliveries survive a restart
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct WebhookQueue {
+    #[serde(skip)]
+    pub data_path: String,
+    pub deliveries: Vec<WebhookDelivery>
+}
+
+impl WebhookQueue {
+
+    pub fn new(data_path: &str) -> WebhookQueue {
+      WebhookQueue {
+        data_path: data_path.to_string(),
+        deliveries: Vec::new()
+      }
+    }
+
+    pub fn save(&self) -> Result<(), Error> {
+      // only write to disk if data path is set
+      if !self.data_path.is_empty() {
+        let queue_path = format!("{}/{}", self.data_path, WEBHOOK_QUEUE_NAME);
+        let mut file = fs::File::create(queue_path)?;
+        let json = serde_json::to_string(self)?;
+        file.write_all(json.as_bytes())?;
+      }
+      Ok(())
+    }
+
+    pub fn read_disk(&mut self) -> Result<(),Error> {
+      let queue_path = format!("{}/{}", &self.data_path, WEBHOOK_QUEUE_NAME);
+      let file = fs::File::open(queue_path)?;
+      let reader = BufReader::new(file);
+      let data: WebhookQueue = serde_json::from_reader(reader)?;
+      self.deliveries = data.deliveries;
+      // deliveries queued before the body was stored were signed before payloads had a message
+      for d in self.deliveries.iter_mut().filter(|d| d.body.is_empty()) {
+        d.body = legacy_body(&d.payload)?;
+      }
+      Ok(())
+    }
+
+    // drop the oldest finished deliveries once the history grows too large
+    fn trim(&mut self) {
+      let finished = self.deliveries.iter()
+        .filter(|d| d.status != DeliveryStatus::Pending)
+        .count();
+      let mut excess = finished.saturating_sub(HISTORY_LIMIT);
+      self.deliveries.retain(|d| {
+        if excess > 0 && d.status != DeliveryStatus::Pending {
+          excess -= 1;
+          return false
+        }
+        true
+      });
+    }
+}
+
+pub struct Webhooks {
+    queue: Arc<Mutex<WebhookQueue>>,
+    // held while the queue is written, one write at a time
+    saving: Arc<Mutex<()>>,
+    notify: Arc<Notify>,
+    client: reqwest::Client,
+    worker: Option<JoinHandle<()>>
+}
+
+impl Webhooks {
+
+    pub fn new(data_path: &str) -> Webhooks {
+      let mut queue = WebhookQueue::new(data_path);
+      queue.read_disk().unwrap_or_default();
+
+      Webhooks {
+        queue: Arc::new(Mutex::new(queue)),
+        saving: Arc::new(Mutex::new(())),
+        notify: Arc::new(Notify::new()),
+        client: reqwest::Client::builder()
+          .timeout(std::time::Duration::from_millis(REQUEST_TIMEOUT_MS))
+          .build()
+          .unwrap(),
+        worker: None
+      }
+    }
+
+    // spawn the background worker which delivers, retries and saves the queue
+    pub fn start(&mut self) {
+      self.stop();
+      let queue = self.queue.clone();
+      let saving = self.saving.clone();
+      let notify = self.notify.clone();
+      let client = self.client.clone();
+      self.worker = Some(tokio::spawn(async move {
+        loop {
+          if process_due(&queue, &client).await {
+            persist(&queue, &saving).await;
+          }
+          tokio::select! {
+            // new deliveries are stored before they are attempted
+            _ = notify.notified() => persist(&queue, &saving).await,
+            _ = tokio::time::sleep(std::time::Duration::from_secs(1)) => {}
+          }
+        }
+      }));
+    }
+
+    pub fn stop(&mut self) {
+      if let Some(w) = &self.worker {
+        w.abort();
+      }
+    }
+
+    // stop the worker after one last attempt at the due deliveries, whatever is
+    // left stays queued on disk for the next start
+    pub async fn shutdown(&mut self) {
+      self.stop();
+      let flush = process_due(&self.queue, &self.client);
+      if tokio::time::timeout(std::time::Duration::from_millis(SHUTDOWN_TIMEOUT_MS), flush).await.is_err() {
+        warn!("pending deliveries are kept for the next start");
+      }
+      persist(&self.queue, &self.saving).await;
+    }
+
+    // queue a delivery of the payload to every active webhook, the worker saves
+    // it so the caller never waits on the disk
+    pub fn enqueue(&self, webhooks: &[WebhookState], payload: WebhookPayload) {
+      let mut queue = self.queue.lock().unwrap();
+      let mut added = false;
+      webhooks.iter()
+        .filter(|w| w.is_active)
+        .for_each(|w| {
+          queue.deliveries.push(WebhookDelivery::new(w, payload.clone()));
+          added = true;
+        });
+      if added {
+        self.notify.notify_one();
+      }
+    }
+
+    pub fn deliveries(&self) -> Vec<WebhookDelivery> {
+      self.queue.lock().unwrap().deliveries.clone()
+    }
+
+    pub async fn process_due(&self) {
+      process_due(&self.queue, &self.client).await;
+    }
+}
+
+// true when a delivery was attempted and the queue needs saving
+async fn process_due(queue: &Arc<Mutex<WebhookQueue>>, client: &reqwest::Client) -> bool {
+    let now = Utc::now();
+    let due: Vec<WebhookDelivery> = {
+      queue.lock().unwrap().deliveries.iter()
+        .filter(|d| d.status == DeliveryStatus::Pending && d.next_attempt <= now)
+        .cloned()
+        .collect()
+    };
+    if due.is_empty() {
+      return false
+    }
+
+    for delivery in due {
+      let result = deliver(client, &delivery).await;
+      if let Err(e) = &result {
+        warn!("delivery {} to {} failed: {}", delivery.id, delivery.url, e);
+      }
+      let mut q = queue.lock().unwrap();
+      if let Some(d) = q.deliveries.iter_mut().find(|d| d.id == delivery.id) {
+        d.record_attempt(result);
+      }
+    }
+
+    queue.lock().unwrap().trim();
+    true
+}
+
+// write the queue on a blocking thread, the snapshot is taken once it is this
+// write's turn so the newest queue always ends up on disk, deliveries stay
+// queued in memory when the disk is unavailable
+async fn persist(queue: &Arc<Mutex<WebhookQueue>>, saving: &Arc<Mutex<()>>) {
+    let (queue, saving) = (queue.clone(), saving.clone());
+    let res = tokio::task::spawn_blocking(move || {
+      let _saving = saving.lock().unwrap_or_else(PoisonError::into_inner);
+      let snapshot = queue.lock().unwrap().clone();
+      snapshot.save()
+    }).await;
+    if let Err(e) = res.map_err(Error::from).and_then(|r| r) {
+      warn!("cannot save queue: {:?}", e);
+    }
+}
+
+async fn deliver(client: &reqwest::Client, delivery: &WebhookDelivery) -> Result<(), String> {
+    let res = client.post(&delivery.url)
+      .header(reqwest::header::CONTENT_TYPE, "application/json")
+      .header(SIGNATURE_HEADER, &delivery.signature)
+      .body(delivery.body.clone())
+      .send()
+      .await
+      .map_err(|e| e.to_string())?;
+
+    if res.status().is_success() {
+      Ok(())
+    } else {
+      Err(format!("unexpected status {}", res.status()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+    use tokio::{io::{AsyncReadExt, AsyncWriteExt}, net::TcpListener, task::JoinHandle};
+
+    use crate::state::WebhookState;
+    use super::{Webhooks, WebhookPayload, WebhookQueue, DeliveryStatus, sign, WEBHOOK_QUEUE_NAME};
+
+    // accept a single request, reply with the given status and hand back the raw request
+    async fn listen_once(status: u16) -> (String, JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let task = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut raw = Vec::new();
+            let mut buf = [0u8; 1024];
+            loop {
+                let n = socket.read(&mut buf).await.unwrap();
+                raw.extend_from_slice(&buf[..n]);
+                let text = String::from_utf8_lossy(&raw).to_string();
+                if let Some(idx) = text.find("\r\n\r\n") {
+                    let length = text.lines()
+                        .find_map(|l| l.to_lowercase().strip_prefix("content-length:").map(|v| v.trim().parse::<usize>().unwrap()))
+                        .unwrap_or(0);
+                    if raw.len() >= idx + 4 + length {
+                        break;
+                    }
+                }
+            }
+            let reply = format!("HTTP/1.1 {} X\r\ncontent-length: 0\r\nconnection: close\r\n\r\n", status);
+            socket.write_all(reply.as_bytes()).await.unwrap();
+            String::from_utf8_lossy(&raw).to_string()
+        });
+        (url, task)
+    }
+
+    fn create_test_payload() -> WebhookPayload {
+        WebhookPayload {
+            reminder_id: "r1".to_string(),
+            reminder_name: "Stretch".to_string(),
+            event: "ReminderNewStatus".to_string(),
+            occurred_at: Utc::now(),
+            next_execution: None,
+            message: None,
+            details: None
+        }
+    }
+
+    #[tokio::test]
+    async fn webhook_delivers_signed_payload() {
+        let (url, server) = listen_once(200).await;
+        let webhook = WebhookState::new(url, "secret".to_string());
+        let webhooks = Webhooks::new("");
+        let payload = create_test_payload();
+
+        webhooks.enqueue(&[webhook], payload.clone());
+        webhooks.process_due().await;
+        let request = server.await.unwrap();
+
+        let body = serde_json::to_string(&payload).unwrap();
+        let signature = sign("secret", &body);
+        assert!(request.starts_with("POST /hook"), "request was {}", request);
+        assert!(request.to_lowercase().contains(&format!("x-paz-signature: {}", signature)), "request was {}", request);
+        assert!(request.ends_with(&body), "request was {}", request);
+
+        let deliveries = webhooks.deliveries();
+        assert_eq!(deliveries.len(), 1);
+        assert_eq!(deliveries[0].status, DeliveryStatus::Delivered);
+        assert_eq!(deliveries[0].attempts, 1);
+    }
+
+    #[tokio::test]
+    async fn webhook_failure_is_retried_later() {
+        let (url, server) = listen_once(500).await;
+        let webhook = WebhookState::new(url, "secret".to_string());
+        let webhooks = Webhooks::new("");
+
+        webhooks.enqueue(&[webhook], create_test_payload());
+        webhooks.process_due().await;
+        server.await.unwrap();
+
+        // the retry is scheduled in the future so nothing is due yet
+        webhooks.process_due().await;
+
+        let deliveries = webhooks.deliveries();
+        assert_eq!(deliveries[0].status, DeliveryStatus::Pending);
+        assert_eq!(deliveries[0].attempts, 1);
+        assert!(deliveries[0].last_error.is_some());
+        assert!(deliveries[0].next_attempt > Utc::now());
+    }
+
+    #[tokio::test]
+    async fn webhook_queue_is_saved_by_the_worker() {
+        let (url, server) = listen_once(500).await;
+        let data_dir = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+        std::fs::create_dir_all(&data_dir).unwrap();
+        let path = data_dir.join(WEBHOOK_QUEUE_NAME);
+        let mut webhooks = Webhooks::new(data_dir.to_str().unwrap());
+        webhooks.start();
+
+        // queueing itself does not touch the disk
+        webhooks.enqueue(&[WebhookState::new(url, "secret".to_string())], create_test_payload());
+        let saved_at_once = path.exists();
+        server.await.unwrap();
+        let mut saved = None;
+        for _ in 0..50 {
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+            let mut queue = WebhookQueue::new(data_dir.to_str().unwrap());
+            if queue.read_disk().is_ok() && matches!(queue.deliveries.first(), Some(d) if d.attempts == 1) {
+                saved = Some(queue);
+                break;
+            }
+        }
+        webhooks.stop();
+        std::fs::remove_dir_all(&data_dir).unwrap_or(());
+
+        assert!(!saved_at_once);
+        assert_eq!(saved.unwrap().deliveries[0].status, DeliveryStatus::Pending);
+    }
+
+    #[tokio::test]
+    async fn webhook_retry_sends_the_signed_body() {
+        let (url, server) = listen_once(200).await;
+        let data_dir = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+        std::fs::create_dir_all(&data_dir).unwrap();
+        // queued by a version whose payloads had no message yet
+        let body = r#"{"reminder_id":"r1","reminder_name":"Stretch","event":"ReminderEnd","occurred_at":"2024-06-03T12:00:00Z","next_execution":null}"#;
+        let queued = serde_json::json!({"deliveries": [{
+            "id": "d1", "webhook_id": "w1", "url": url, "payload": serde_json::from_str::<serde_json::Value>(body).unwrap(),
+            "signature": sign("secret", body), "status": "Pending", "attempts": 1, "last_error": "timeout",
+            "last_attempt": null, "next_attempt": "2024-06-03T12:00:00Z"
+        }]});
+        std::fs::write(data_dir.join(WEBHOOK_QUEUE_NAME), queued.to_string()).unwrap();
+
+        let webhooks = Webhooks::new(data_dir.to_str().unwrap());
+        webhooks.process_due().await;
+        let request = server.await.unwrap();
+        std::fs::remove_dir_all(&data_dir).unwrap_or(());
+
+        assert!(request.ends_with(body), "request was {}", request);
+        assert!(request.to_lowercase().contains(&format!("x-paz-signature: {}", sign("secret", body))), "request was {}", request);
+        assert_eq!(webhooks.deliveries()[0].status, DeliveryStatus::Delivered);
+    }
+
+    #[test]
+    fn webhook_inactive_not_queued() {
+        let mut webhook = WebhookState::new("http://localhost".to_string(), "secret".to_string());
+        webhook.is_active = false;
+        let webhooks = Webhooks::new("");
+
+        webhooks.enqueue(&[webhook], create_test_payload());
+
+        assert!(webhooks.deliveries().is_empty());
+    }
+}
@@ -1,9 +1,10 @@
 #![cfg_attr(all(not(debug_assertions), target_os = "windows"), windows_subsystem = "windows")]
 
-use pazcore::CoreEvent;
+use pazcore::{CoreEvent, events::{EventFilter, Subscription}};
 pub(crate) 
-use pazcore::{Core, CoreController, ClientCommand, ClientQuery, CoreResponse};
+use pazcore::{Core, CoreController, CoreError, ClientCommand, ClientQuery, CoreResponse};
 use tauri::api::path;
+use tracing::{debug, error, info, warn};
 use tauri::{
   SystemTray, 
   CustomMenuItem, 
@@ -19,23 +20,23 @@ use tauri::{
 
 
 #[tauri::command(async)]
-async fn client_query(core: tauri::State<'_, CoreController>, data: ClientQuery) -> Result<CoreResponse, String> {
+async fn client_query(core: tauri::State<'_, CoreController>, data: ClientQuery) -> Result<CoreResponse, CoreError> {
   match core.query(data).await {
     Ok(response) => Ok(response),
     Err(err) => {
-      println!("Error: Query: {:?}", err);
-      Err(err.to_string())
+      error!("query failed: {:?}", err);
+      Err(err)
     }
   }
 }
 
 #[tauri::command(async)]
-async fn client_command(core: tauri::State<'_, CoreController>, data: ClientCommand) -> Result<CoreResponse, String> {
+async fn client_command(core: tauri::State<'_, CoreController>, data: ClientCommand) -> Result<CoreResponse, CoreError> {
   match core.command(data).await {
     Ok(response) => Ok(response),
     Err(err) => {
-      println!("Error: Command: {:?}", err);
-      Err(err.to_string())
+      error!("command failed: {:?}", err);
+      Err(err)
     }
   }
 }
@@ -48,11 +49,26 @@ async fn main() {
 
   // instantiate core
   let data_dir = path::data_dir().unwrap_or(std::path::PathBuf::from("./"));
-  let (mut core, mut event_reciever) = Core::new(data_dir);
+  let mut core = Core::new(data_dir.clone());
+  let event_reciever = core.subscribe(EventFilter::default());
   let controller = core.get_controller();
+  let start_minimized = core.settings().startup.start_minimized;
   // init connections/network resources
   core.initialize();
 
+  // let scripts and other local tools control the core
+  #[cfg(unix)]
+  {
+    let ipc_controller = controller.clone();
+    tokio::spawn(async move {
+      if let Err(e) = pazcore::ipc::serve(pazcore::ipc::socket_path(&data_dir), ipc_controller).await {
+        error!("ipc: {:?}", e);
+      }
+    });
+  }
+  // optional localhost http api, only listens when enabled in the client state
+  tokio::spawn(pazcore::http::start(controller.clone()));
+
 
   // core.start in a background thread
   tokio::spawn(async move {
@@ -78,8 +94,14 @@ async fn main() {
 
   // build app
   let app = tauri::Builder::default()
-    .setup(|app| {
+    .setup(move |app| {
       run_event_reciever(app.handle(), event_reciever);
+      // only the tray icon is shown, it opens the window again
+      if start_minimized {
+        if let Some(w) = app.get_window("main") {
+          w.close()?;
+        }
+      }
       Ok(())
     })
     .manage(controller)
@@ -93,16 +115,18 @@ async fn main() {
   app.run(run_event_handler);
 }
 
-pub fn run_event_reciever(app: AppHandle, mut receiver: tokio::sync::mpsc::Receiver<CoreEvent>) {
+pub fn run_event_reciever(app: AppHandle, mut receiver: Subscription) {
   tokio::spawn(async move {
     while let Some(event) = receiver.recv().await {
-      println!("INFO: NEW Reminder Status");
+      debug!("forwarding {} to the ui", event.name());
       match event {
-          CoreEvent::ReminderNewStatus { id: _, next_duration_ms: _ } => {
+          CoreEvent::ReminderNewStatus { .. } | CoreEvent::ReminderEnd { id: _ } | CoreEvent::ReminderIntervalAdapted { .. } | CoreEvent::StateChanged { .. } | CoreEvent::EngineError { .. } | CoreEvent::ShuttingDown | CoreEvent::Lagged { .. } => {
             app.emit_all("core_event", &event).unwrap();
           }
       }
     }
+    // the subscription ends once the core has shut down
+    app.exit(0);
   });
 }
 
@@ -118,24 +142,38 @@ pub fn run_event_handler(_: &AppHandle, event: RunEvent) {
 
 pub fn system_tray_event_handler(handler: &AppHandle, event: SystemTrayEvent) {
   match event {
-    SystemTrayEvent::DoubleClick { position: _, size: _, .. } => println!("Info: SysTray: DoubleClick"),
-    SystemTrayEvent::RightClick { position: _, size: _, .. } => println!("Info: SysTray: RightClick"),
-    SystemTrayEvent::LeftClick { position: _, size: _, .. } => println!("Info: SysTray: LeftClick"),
+    SystemTrayEvent::DoubleClick { position: _, size: _, .. } => info!("tray double clicked"),
+    SystemTrayEvent::RightClick { position: _, size: _, .. } => info!("tray right clicked"),
+    SystemTrayEvent::LeftClick { position: _, size: _, .. } => info!("tray left clicked"),
     SystemTrayEvent::MenuItemClick { id, .. } => {
       match id.as_str() {
-          "exit" => handler.exit(0),
+          "exit" => shutdown_event_handler(handler),
           "open" => open_app_event_handler(handler),
-          _ => println!("Info: SysTray: {0} Clicked", id)
+          _ => info!("tray item {0} clicked", id)
       }
     }
     _ => todo!(),
   }
 }
 
+// let the core stop its reminders and flush its state before the app exits
+pub fn shutdown_event_handler(handler: &AppHandle) {
+  let handler = handler.clone();
+  let controller = handler.state::<CoreController>().inner().clone();
+  tokio::spawn(async move {
+    match tokio::time::timeout(std::time::Duration::from_secs(10), controller.shutdown()).await {
+      Ok(Ok(_)) => {},
+      Ok(Err(e)) => error!("shutdown failed: {}", e),
+      Err(_) => error!("shutdown timed out")
+    }
+    handler.exit(0);
+  });
+}
+
 pub fn open_app_event_handler(handler: &AppHandle) {
 
   if let Some(w) = handler.get_window("main") {
-    println!("Warn: SysTray: The window is already open");
+    warn!("the window is already open");
     w.set_focus().unwrap();
     return;
   }
@@ -157,6 +195,6 @@ pub fn open_app_event_handler(handler: &AppHandle) {
       return;
   }
 
-  println!("Error: SysTray: Cannot find window config")
+  error!("cannot find window config")
 }
 
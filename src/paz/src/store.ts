@@ -4,4 +4,4 @@ import { Transport } from './transport';
 
 export const state = writable({} as ClientState);
 export const transport = readable(new  Transport());
-export const reminderStatusEvent = writable({ReminderNewStatus: {id: "", next_duration_ms: -1}} as CoreEvent)
\ No newline at end of file
+export const reminderStatusEvent = writable({ReminderNewStatus: {id: "", next_duration_ms: -1, message: {title: "", body: ""}, exercises: []}} as CoreEvent)
\ No newline at end of file
@@ -1,4 +1,4 @@
-import type { ClientCommand, ClientQuery, CoreEvent } from "@paz/core";
+import type { ClientCommand, ClientQuery, CoreError, CoreEvent } from "@paz/core";
 import { invoke } from "@tauri-apps/api";
 import { EventCallback, listen, UnlistenFn } from "@tauri-apps/api/event";
 
@@ -19,4 +19,24 @@ export class Transport {
     subscribe(callback: EventCallback<CoreEvent> ): Promise<UnlistenFn> {
         return listen<CoreEvent>('core_event', callback)
     }
+}
+
+// commands and queries reject with a CoreError, anything else is a tauri failure
+export const isCoreError = (e: unknown): e is CoreError =>
+    typeof e === "object" && e !== null && "code" in e
+
+export const describeError = (e: unknown): string => {
+    if (!isCoreError(e)) {
+        return `${e}`
+    }
+    switch (e.code) {
+        case "storage": return `Could not save: ${e.details.reason}`
+        case "validation": return `Invalid ${e.details.field}: ${e.details.reason}`
+        case "not_found": return `No ${e.details.resource} with id ${e.details.id}`
+        case "integration": return `${e.details.integration}: ${e.details.reason}`
+        case "scheduler": return `Could not schedule: ${e.details.reason}`
+        case "invalid_request": return `Invalid request: ${e.details.reason}`
+        case "internal": return `Something went wrong: ${e.details.reason}`
+        case "unavailable": return "paz is not running"
+    }
 }
\ No newline at end of file
@@ -0,0 +1,570 @@
+use clap::{Parser, Subcommand};
+use pazcore::{ClientCommand, ClientQuery, CoreEvent, CoreResponse, config::{ConfigFormat, ImportMode}, events::EventFilter, ipc, once::OnceTarget, state::{ClientState, ProfileState, ReminderPatch, ReminderState}};
+use std::path::PathBuf;
+
+use crate::client::Client;
+use crate::format::{format_duration, format_next, parse_duration, print_table};
+
+// shorter id prefixes are rejected, they too easily pick the wrong reminder for e.g. remove
+const MIN_PREFIX_LEN: usize = 4;
+
+#[derive(Parser)]
+#[clap(name = "pazctl", version, about = "Control and observe a running paz instance")]
+struct Cli {
+    /// Print JSON instead of human readable tables
+    #[clap(long, global = true)]
+    json: bool,
+
+    /// Path of the control socket, defaults to $PAZ_SOCKET or the one in the data directory
+    #[clap(long, global = true)]
+    socket: Option<PathBuf>,
+
+    #[clap(subcommand)]
+    command: Command
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// List reminders and when they fire next
+    List,
+    /// Add a new reminder
+    Add {
+        name: String,
+        /// How long to wait between reminders, e.g. 45m
+        #[clap(long)]
+        every: String,
+        /// How long the break lasts, e.g. 30s
+        #[clap(long)]
+        duration: String,
+        /// Add the reminder without starting it
+        #[clap(long)]
+        inactive: bool
+    },
+    /// Add a reminder which fires once, e.g. --at 16:00 or --in 90m
+    Once {
+        name: String,
+        /// Local time as HH:MM, today unless --on is given
+        #[clap(long, conflicts_with = "after", required_unless_present = "after")]
+        at: Option<String>,
+        /// Date as YYYY-MM-DD to go with --at
+        #[clap(long, requires = "at")]
+        on: Option<String>,
+        /// How long from now, e.g. 90m
+        #[clap(long = "in")]
+        after: Option<String>,
+        /// How long the break lasts, e.g. 30s
+        #[clap(long, default_value = "1m")]
+        duration: String,
+        /// Delete the reminder once its break ended
+        #[clap(long)]
+        delete: bool
+    },
+    /// Change an existing reminder
+    Edit {
+        /// Id, id prefix or name of the reminder
+        reminder: String,
+        #[clap(long)]
+        name: Option<String>,
+        #[clap(long)]
+        every: Option<String>,
+        #[clap(long)]
+        duration: Option<String>,
+        #[clap(long)]
+        active: Option<bool>
+    },
+    /// Delete a reminder
+    Delete {
+        reminder: String
+    },
+    /// Pause one reminder, or every reminder when none is given
+    Pause {
+        reminder: Option<String>
+    },
+    /// Resume one reminder, or every reminder when none is given
+    Resume {
+        reminder: Option<String>
+    },
+    /// Push the next occurrence of a reminder back
+    Delay {
+        reminder: String,
+        /// How long to delay by, e.g. 10m
+        by: String
+    },
+    /// Skip the current occurrence and wait a full interval
+    Skip {
+        reminder: String
+    },
+    /// Log water drunk through a hydration reminder
+    Drink {
+        reminder: String,
+        /// Millilitres, defaults to the reminder's own amount
+        amount: Option<i32>
+    },
+    /// Print the water logged per day
+    Intake {
+        #[clap(long, default_value = "7")]
+        days: u32
+    },
+    /// List profiles, the active one is marked
+    Profiles,
+    /// Switch to another profile, its reminders replace the running ones
+    Switch {
+        /// Id, id prefix or name of the profile
+        profile: String
+    },
+    /// List the preset reminders
+    Presets,
+    /// Add the reminder of a preset, e.g. eye-20-20-20
+    Apply {
+        preset: String
+    },
+    /// Print events as the core emits them
+    Events {
+        /// Only print events of this kind, e.g. ReminderEnd
+        #[clap(long = "event")]
+        events: Vec<String>,
+        /// Only print events about this reminder
+        #[clap(long = "reminder")]
+        reminders: Vec<String>
+    },
+    /// Print the core's most recent log records, e.g. for a bug report
+    Logs {
+        /// How many records to print
+        #[clap(long, default_value = "200")]
+        limit: usize
+    },
+    /// Print the configuration with readable durations, e.g. to keep it in a dotfiles repo
+    Export {
+        /// toml or yaml
+        #[clap(long, default_value = "toml")]
+        format: String
+    },
+    /// Load a configuration written by export
+    Import {
+        /// Path of the TOML or YAML file, - reads stdin
+        file: PathBuf,
+        /// Drop reminders and webhooks which are not in the file
+        #[clap(long)]
+        replace: bool
+    },
+    /// Stop the core after it has flushed its state
+    Shutdown
+}
+
+pub fn main() {
+    let cli = Cli::parse();
+    if let Err(e) = run(cli) {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    }
+}
+
+fn run(cli: Cli) -> Result<(), String> {
+    let path = cli.socket.clone()
+        .or_else(|| std::env::var_os("PAZ_SOCKET").map(PathBuf::from))
+        .unwrap_or_else(|| ipc::socket_path(&dirs::data_dir().unwrap_or_else(|| PathBuf::from("./"))));
+    let mut client = Client::connect(&path)?;
+
+    match cli.command {
+        Command::List => list(&mut client, cli.json),
+        Command::Add { name, every, duration, inactive } => {
+            let added = reminder_command(&mut client, ClientCommand::CreateReminder {
+                name,
+                wait_ms: parse_duration(&every)?,
+                duration_ms: parse_duration(&duration)?,
+                is_active: !inactive
+            })?;
+            print_reminder("Added", &added, cli.json);
+            Ok(())
+        },
+        Command::Once { name, at, on, after, duration, delete } => {
+            let target = match (at, after) {
+                (Some(time), _) => OnceTarget::At { time, date: on },
+                (None, Some(after)) => OnceTarget::In { delay_ms: parse_duration(&after)? },
+                (None, None) => return Err("either --at or --in is needed".to_string())
+            };
+            let added = reminder_command(&mut client, ClientCommand::ScheduleOnce {
+                name,
+                target,
+                duration_ms: parse_duration(&duration)?,
+                delete_when_done: delete
+            })?;
+            print_reminder("Added", &added, cli.json);
+            if let (Some(once), false) = (&added.once, cli.json) {
+                println!("Fires at {}", once.at.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M"));
+            }
+            Ok(())
+        },
+        Command::Edit { reminder, name, every, duration, active } => {
+            let state = get_state(&mut client)?;
+            let id = state.reminders[resolve(&state.reminders, &reminder)?].id.clone();
+            let patch = ReminderPatch {
+                name,
+                is_active: active,
+                wait_ms: every.map(|e| parse_duration(&e)).transpose()?,
+                duration_ms: duration.map(|d| parse_duration(&d)).transpose()?,
+                ..ReminderPatch::default()
+            };
+            let edited = reminder_command(&mut client, ClientCommand::UpdateReminder { id, patch: Box::new(patch) })?;
+            print_reminder("Updated", &edited, cli.json);
+            Ok(())
+        },
+        Command::Delete { reminder } => {
+            let state = get_state(&mut client)?;
+            let id = state.reminders[resolve(&state.reminders, &reminder)?].id.clone();
+            let deleted = reminder_command(&mut client, ClientCommand::DeleteReminder { id })?;
+            print_reminder("Deleted", &deleted, cli.json);
+            Ok(())
+        },
+        Command::Pause { reminder } => set_active(&mut client, reminder, false, cli.json),
+        Command::Resume { reminder } => set_active(&mut client, reminder, true, cli.json),
+        Command::Delay { reminder, by } => {
+            let state = get_state(&mut client)?;
+            let r = &state.reminders[resolve(&state.reminders, &reminder)?];
+            let res = client.command(ClientCommand::DelayReminder { id: r.id.clone(), delay: parse_duration(&by)? })?;
+            print_response(&res, &format!("Delayed {} by {}", r.name, by), cli.json);
+            Ok(())
+        },
+        Command::Skip { reminder } => {
+            let state = get_state(&mut client)?;
+            let r = &state.reminders[resolve(&state.reminders, &reminder)?];
+            let res = client.command(ClientCommand::SkipReminder { id: r.id.clone() })?;
+            print_response(&res, &format!("Skipped {}", r.name), cli.json);
+            Ok(())
+        },
+        Command::Drink { reminder, amount } => {
+            let state = get_state(&mut client)?;
+            let r = &state.reminders[resolve(&state.reminders, &reminder)?];
+            let res = client.command(ClientCommand::LogIntake { id: r.id.clone(), amount_ml: amount })?;
+            let message = match &res {
+                CoreResponse::IntakeStats(days) if !days.is_empty() => format!("{} ml of {} ml today", days[0].amount_ml, days[0].goal_ml),
+                _ => "Logged".to_string()
+            };
+            print_response(&res, &message, cli.json);
+            Ok(())
+        },
+        Command::Intake { days } => {
+            let days = match client.query(ClientQuery::IntakeGetStats { days: Some(days) })? {
+                CoreResponse::IntakeStats(d) => d,
+                other => return Err(format!("unexpected response {:?}", other))
+            };
+            if cli.json {
+                println!("{}", serde_json::to_string_pretty(&days).unwrap());
+                return Ok(())
+            }
+            let rows = days.iter().map(|d| vec![
+                d.date.clone(),
+                format!("{} ml", d.amount_ml),
+                format!("{}%", i64::from(d.amount_ml) * 100 / i64::from(d.goal_ml.max(1)))
+            ]).collect();
+            print_table(&["DATE", "INTAKE", "% GOAL"], rows);
+            Ok(())
+        },
+        Command::Profiles => {
+            let state = get_state(&mut client)?;
+            if cli.json {
+                println!("{}", serde_json::to_string_pretty(&state.profiles).unwrap());
+                return Ok(())
+            }
+            let rows = state.profiles.iter().map(|p| vec![
+                short_id(&p.id),
+                p.name.clone(),
+                if p.id == state.active_profile { "yes".to_string() } else { String::new() },
+                p.rules.len().to_string()
+            ]).collect();
+            print_table(&["ID", "NAME", "ACTIVE", "RULES"], rows);
+            Ok(())
+        },
+        Command::Switch { profile } => {
+            let state = get_state(&mut client)?;
+            let p = &state.profiles[resolve_profile(&state.profiles, &profile)?];
+            let res = client.command(ClientCommand::SwitchProfile { id: p.id.clone() })?;
+            print_response(&res, &format!("Switched to {}", p.name), cli.json);
+            Ok(())
+        },
+        Command::Presets => {
+            let presets = match client.query(ClientQuery::PresetGetCatalogue)? {
+                CoreResponse::Presets(p) => p,
+                other => return Err(format!("unexpected response {:?}", other))
+            };
+            if cli.json {
+                println!("{}", serde_json::to_string_pretty(&presets).unwrap());
+                return Ok(())
+            }
+            let rows = presets.iter().map(|p| vec![
+                p.id.clone(),
+                p.name.clone(),
+                format_duration(i64::from(p.wait_ms)),
+                format_duration(i64::from(p.duration_ms)),
+                p.description.clone()
+            ]).collect();
+            print_table(&["ID", "NAME", "EVERY", "BREAK", "DESCRIPTION"], rows);
+            Ok(())
+        },
+        Command::Apply { preset } => {
+            let res = client.command(ClientCommand::ApplyPreset { id: preset })?;
+            let message = match &res {
+                CoreResponse::Reminder(r) => format!("Added {}", r.name),
+                _ => "Added".to_string()
+            };
+            print_response(&res, &message, cli.json);
+            Ok(())
+        },
+        Command::Events { events: kinds, reminders } => {
+            let state = get_state(&mut client)?;
+            let reminder_ids = reminders.iter()
+                .map(|r| resolve(&state.reminders, r).map(|i| state.reminders[i].id.clone()))
+                .collect::<Result<Vec<String>, String>>()?;
+            events(&mut client, EventFilter { events: kinds, reminder_ids }, cli.json)
+        },
+        Command::Logs { limit } => logs(&mut client, limit, cli.json),
+        Command::Export { format } => {
+            let format = match format.as_str() {
+                "toml" => ConfigFormat::Toml,
+                "yaml" | "yml" => ConfigFormat::Yaml,
+                other => return Err(format!("unknown format '{}', expected toml or yaml", other))
+            };
+            match client.command(ClientCommand::ExportConfig { format })? {
+                CoreResponse::Config(content) => print!("{}", content),
+                other => return Err(format!("unexpected response {:?}", other))
+            }
+            Ok(())
+        },
+        Command::Import { file, replace } => {
+            let content = if file.as_os_str() == "-" {
+                std::io::read_to_string(std::io::stdin()).map_err(|e| e.to_string())?
+            } else {
+                std::fs::read_to_string(&file).map_err(|e| format!("could not read {}: {}", file.display(), e))?
+            };
+            let mode = if replace { ImportMode::Replace } else { ImportMode::Merge };
+            let report = match client.command(ClientCommand::ImportConfig { content, mode })? {
+                CoreResponse::ConfigImported(r) => r,
+                other => return Err(format!("unexpected response {:?}", other))
+            };
+            if cli.json {
+                println!("{}", serde_json::to_string_pretty(&report).unwrap());
+                return Ok(())
+            }
+            println!("Imported: {} added, {} updated, {} removed", report.added.len(), report.updated.len(), report.removed.len());
+            for c in report.conflicts {
+                println!("Skipped {} ({}): {}", c.entry, c.name, c.reason);
+            }
+            Ok(())
+        },
+        Command::Shutdown => {
+            let res = client.command(ClientCommand::Shutdown)?;
+            print_response(&res, "Stopped paz", cli.json);
+            Ok(())
+        }
+    }
+}
+
+fn logs(client: &mut Client, limit: usize, json: bool) -> Result<(), String> {
+    let records = match client.query(ClientQuery::GetRecentLogs { limit: Some(limit) })? {
+        CoreResponse::Logs(r) => r,
+        other => return Err(format!("unexpected response {:?}", other))
+    };
+    for r in records {
+        if json {
+            println!("{}", serde_json::to_string(&r).unwrap());
+            continue;
+        }
+        let time = r.timestamp.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S");
+        let level = format!("{:?}", r.level).to_uppercase();
+        let spans = r.spans.join(":");
+        println!("{} {:5} {} {}{}", time, level, r.target, if spans.is_empty() { String::new() } else { format!("{}: ", spans) }, r.message);
+    }
+    Ok(())
+}
+
+fn list(client: &mut Client, json: bool) -> Result<(), String> {
+    let schedule = match client.query(ClientQuery::ReminderGetSchedule)? {
+        CoreResponse::ReminderGetSchedule(s) => s,
+        other => return Err(format!("unexpected response {:?}", other))
+    };
+    if json {
+        println!("{}", serde_json::to_string_pretty(&schedule).unwrap());
+        return Ok(())
+    }
+    let rows = schedule.into_iter().map(|s| vec![
+        short_id(&s.id),
+        s.name,
+        if s.is_active { "yes".to_string() } else { "no".to_string() },
+        format_duration(i64::from(s.wait_ms)),
+        format_duration(i64::from(s.duration_ms)),
+        format_next(s.next_execution)
+    ]).collect();
+    print_table(&["ID", "NAME", "ACTIVE", "EVERY", "DURATION", "NEXT"], rows);
+    Ok(())
+}
+
+fn events(client: &mut Client, filter: EventFilter, json: bool) -> Result<(), String> {
+    client.subscribe(filter)?;
+    loop {
+        let event = client.next_event()?;
+        if json {
+            println!("{}", serde_json::to_string(&event).unwrap());
+            continue;
+        }
+        let now = chrono::Local::now().format("%H:%M:%S");
+        match event {
+            CoreEvent::ReminderNewStatus { id, next_duration_ms, message, exercises } => {
+                println!("{}  start  {}  next in {}  {}: {}", now, short_id(&id), format_duration(i64::from(next_duration_ms)), message.title, message.body);
+                for exercise in exercises {
+                    println!("          {} ({})", exercise.title, format_duration(i64::from(exercise.duration_ms)))
+                }
+            },
+            CoreEvent::ReminderEnd { id } => println!("{}  end  {}", now, short_id(&id)),
+            CoreEvent::ReminderIntervalAdapted { id, reason, .. } => println!("{}  adapted  {}  {}", now, short_id(&id), reason),
+            CoreEvent::StateChanged { reminder_id } => {
+                println!("{}  changed  {}", now, reminder_id.as_deref().map(short_id).unwrap_or_else(|| "all".to_string()))
+            },
+            CoreEvent::EngineError { reminder_id, error } => {
+                println!("{}  error  {}  {}", now, reminder_id.as_deref().map(short_id).unwrap_or_else(|| "-".to_string()), error)
+            },
+            CoreEvent::ShuttingDown => {
+                println!("{}  shutdown", now);
+                return Ok(())
+            },
+            CoreEvent::Lagged { skipped } => println!("{}  missed {} events", now, skipped)
+        }
+    }
+}
+
+// pause or resume a single reminder, or the whole core
+fn set_active(client: &mut Client, reminder: Option<String>, active: bool, json: bool) -> Result<(), String> {
+    let verb = if active { "Resumed" } else { "Paused" };
+    match reminder {
+        None => {
+            let res = client.command(ClientCommand::SetPaused { paused: !active })?;
+            print_response(&res, &format!("{} all reminders", verb), json);
+        },
+        Some(reminder) => {
+            let state = get_state(client)?;
+            let id = state.reminders[resolve(&state.reminders, &reminder)?].id.clone();
+            let changed = reminder_command(client, ClientCommand::SetReminderActive { id, is_active: active })?;
+            print_reminder(verb, &changed, json);
+        }
+    }
+    Ok(())
+}
+
+fn get_state(client: &mut Client) -> Result<ClientState, String> {
+    match client.query(ClientQuery::ClientGetState)? {
+        CoreResponse::ClientGetState(s) => Ok(*s),
+        other => Err(format!("unexpected response {:?}", other))
+    }
+}
+
+// run a command which answers with the reminder it changed
+fn reminder_command(client: &mut Client, cmd: ClientCommand) -> Result<ReminderState, String> {
+    match client.command(cmd)? {
+        CoreResponse::Reminder(r) => Ok(r),
+        other => Err(format!("unexpected response {:?}", other))
+    }
+}
+
+// find a reminder by its id, a unique prefix of its id, or its name
+// an exact id or name, or an id prefix long enough not to match by accident
+fn resolve(reminders: &[ReminderState], needle: &str) -> Result<usize, String> {
+    if let Some(idx) = reminders.iter().position(|r| r.id == needle) {
+        return Ok(idx)
+    }
+    let matches: Vec<usize> = reminders.iter()
+        .enumerate()
+        .filter(|(_, r)| is_prefix(&r.id, needle) || r.name.eq_ignore_ascii_case(needle))
+        .map(|(i, _)| i)
+        .collect();
+    match matches.len() {
+        0 if needle.len() < MIN_PREFIX_LEN => Err(format!("no reminder matches '{}', use at least {} characters of the id", needle, MIN_PREFIX_LEN)),
+        0 => Err(format!("no reminder matches '{}'", needle)),
+        1 => Ok(matches[0]),
+        _ => Err(format!("'{}' matches more than one reminder, use a longer id", needle))
+    }
+}
+
+fn resolve_profile(profiles: &[ProfileState], needle: &str) -> Result<usize, String> {
+    if let Some(idx) = profiles.iter().position(|p| p.id == needle) {
+        return Ok(idx)
+    }
+    let matches: Vec<usize> = profiles.iter()
+        .enumerate()
+        .filter(|(_, p)| is_prefix(&p.id, needle) || p.name.eq_ignore_ascii_case(needle))
+        .map(|(i, _)| i)
+        .collect();
+    match matches.len() {
+        0 if needle.len() < MIN_PREFIX_LEN => Err(format!("no profile matches '{}', use at least {} characters of the id", needle, MIN_PREFIX_LEN)),
+        0 => Err(format!("no profile matches '{}'", needle)),
+        1 => Ok(matches[0]),
+        _ => Err(format!("'{}' matches more than one profile, use a longer id", needle))
+    }
+}
+
+fn is_prefix(id: &str, needle: &str) -> bool {
+    needle.len() >= MIN_PREFIX_LEN && id.starts_with(needle)
+}
+
+fn short_id(id: &str) -> String {
+    id.chars().take(8).collect()
+}
+
+fn print_reminder(verb: &str, reminder: &ReminderState, json: bool) {
+    if json {
+        println!("{}", serde_json::to_string_pretty(reminder).unwrap());
+    } else {
+        println!("{} {} ({})", verb, reminder.name, short_id(&reminder.id));
+    }
+}
+
+fn print_response(res: &CoreResponse, message: &str, json: bool) {
+    if json {
+        println!("{}", serde_json::to_string_pretty(res).unwrap());
+    } else {
+        println!("{}", message);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pazcore::state::{ProfileState, ReminderState};
+    use super::{resolve, resolve_profile};
+
+    fn reminder(id: &str, name: &str) -> ReminderState {
+        let mut r = ReminderState::new(name.to_string(), 60 * 1000, 1000);
+        r.id = id.to_string();
+        r
+    }
+
+    fn profile(id: &str, name: &str) -> ProfileState {
+        let mut p = ProfileState::new(name.to_string());
+        p.id = id.to_string();
+        p
+    }
+
+    #[test]
+    fn resolve_by_id_prefix_or_name() {
+        let reminders = vec![reminder("ab12ef", "Stretch"), reminder("ab34ef", "Eyes"), reminder("cd56ef", "Water"), reminder("ef", "Gym")];
+        assert_eq!(resolve(&reminders, "ab34ef"), Ok(1));
+        assert_eq!(resolve(&reminders, "cd56"), Ok(2));
+        assert_eq!(resolve(&reminders, "stretch"), Ok(0));
+        // an exact id is fine however short
+        assert_eq!(resolve(&reminders, "ef"), Ok(3));
+    }
+
+    #[test]
+    fn resolve_rejects_unknown_and_ambiguous() {
+        let reminders = vec![reminder("ab12ef", "Stretch"), reminder("ab12cd", "Eyes")];
+        assert_eq!(resolve(&reminders, "zzzz"), Err("no reminder matches 'zzzz'".to_string()));
+        assert_eq!(resolve(&reminders, "ab12"), Err("'ab12' matches more than one reminder, use a longer id".to_string()));
+        assert!(resolve(&[], "ab12").is_err());
+    }
+
+    #[test]
+    fn resolve_rejects_short_prefixes() {
+        let reminders = vec![reminder("ab12ef", "Stretch")];
+        assert_eq!(resolve(&reminders, ""), Err("no reminder matches '', use at least 4 characters of the id".to_string()));
+        assert_eq!(resolve(&reminders, "ab1"), Err("no reminder matches 'ab1', use at least 4 characters of the id".to_string()));
+        assert!(resolve_profile(&[profile("ab12ef", "Work")], "").is_err());
+        assert_eq!(resolve_profile(&[profile("ab12ef", "Work")], "work"), Ok(0));
+    }
+}
@@ -0,0 +1,70 @@
+use pazcore::{ClientCommand, ClientQuery, CoreEvent, CoreResponse, events::EventFilter, ipc::{IpcMessage, IpcRequest}};
+use std::{io::{BufRead, BufReader, Write}, os::unix::net::UnixStream, path::Path};
+
+// a blocking connection to the core's control socket
+pub struct Client {
+    reader: BufReader<UnixStream>,
+    writer: UnixStream,
+    // events which arrived while waiting for a response
+    pending: Vec<CoreEvent>
+}
+
+impl Client {
+
+    pub fn connect(path: &Path) -> Result<Client, String> {
+        let stream = UnixStream::connect(path)
+            .map_err(|e| format!("cannot connect to {}, is paz running? ({})", path.display(), e))?;
+        let writer = stream.try_clone().map_err(|e| e.to_string())?;
+        Ok(Client {
+            reader: BufReader::new(stream),
+            writer,
+            pending: Vec::new()
+        })
+    }
+
+    pub fn query(&mut self, query: ClientQuery) -> Result<CoreResponse, String> {
+        self.request(IpcRequest::Query(query))
+    }
+
+    pub fn command(&mut self, cmd: ClientCommand) -> Result<CoreResponse, String> {
+        self.request(IpcRequest::Command(cmd))
+    }
+
+    pub fn subscribe(&mut self, filter: EventFilter) -> Result<(), String> {
+        self.request(IpcRequest::Subscribe(Some(filter))).map(|_| ())
+    }
+
+    // block until the core emits the next event
+    pub fn next_event(&mut self) -> Result<CoreEvent, String> {
+        if !self.pending.is_empty() {
+            return Ok(self.pending.remove(0))
+        }
+        loop {
+            if let IpcMessage::Event(e) = self.read()? {
+                return Ok(e)
+            }
+        }
+    }
+
+    fn request(&mut self, req: IpcRequest) -> Result<CoreResponse, String> {
+        let mut json = serde_json::to_string(&req).map_err(|e| e.to_string())?;
+        json.push('\n');
+        self.writer.write_all(json.as_bytes()).map_err(|e| e.to_string())?;
+        loop {
+            match self.read()? {
+                IpcMessage::Response(r) => return Ok(r),
+                IpcMessage::Error(e) => return Err(e.to_string()),
+                IpcMessage::Event(e) => self.pending.push(e)
+            }
+        }
+    }
+
+    fn read(&mut self) -> Result<IpcMessage, String> {
+        let mut line = String::new();
+        let n = self.reader.read_line(&mut line).map_err(|e| e.to_string())?;
+        if n == 0 {
+            return Err("paz closed the connection".to_string())
+        }
+        serde_json::from_str(&line).map_err(|e| format!("invalid message from paz: {}", e))
+    }
+}
@@ -0,0 +1,66 @@
+use chrono::{DateTime, Local, Utc};
+
+// shared with the config format of the core
+pub use pazcore::duration::{format_duration, parse_duration};
+
+pub fn format_next(next: Option<DateTime<Utc>>) -> String {
+    match next {
+        None => "-".to_string(),
+        Some(n) => {
+            let remaining = (n - Utc::now()).num_milliseconds().max(0);
+            format!("in {} ({})", format_duration(remaining), n.with_timezone(&Local).format("%H:%M:%S"))
+        }
+    }
+}
+
+// print rows as left aligned columns
+pub fn print_table(headers: &[&str], rows: Vec<Vec<String>>) {
+    print!("{}", table(headers, rows));
+}
+
+fn table(headers: &[&str], rows: Vec<Vec<String>>) -> String {
+    let mut widths: Vec<usize> = headers.iter().map(|h| h.len()).collect();
+    for row in &rows {
+        for (i, cell) in row.iter().enumerate() {
+            widths[i] = widths[i].max(cell.chars().count());
+        }
+    }
+    let line = |cells: Vec<String>| {
+        let padded: Vec<String> = cells.iter()
+            .enumerate()
+            .map(|(i, c)| format!("{:width$}", c, width = widths[i]))
+            .collect();
+        format!("{}\n", padded.join("  ").trim_end())
+    };
+    let mut out = line(headers.iter().map(|h| h.to_string()).collect());
+    rows.into_iter().for_each(|row| out.push_str(&line(row)));
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{format_duration, format_next, table};
+
+    #[test]
+    fn durations_are_short() {
+        assert_eq!(format_duration(500), "500ms");
+        assert_eq!(format_duration(45 * 1000), "45s");
+        assert_eq!(format_duration(20 * 60 * 1000), "20m");
+        assert_eq!(format_duration(90 * 60 * 1000), "1h30m");
+        assert_eq!(format_duration(60 * 60 * 1000 + 1500), "1h1s500ms");
+        assert_eq!(format_next(None), "-");
+    }
+
+    #[test]
+    fn table_columns_line_up() {
+        let rows = vec![
+            vec!["a1b2c3d4".to_string(), "Stretch".to_string(), "yes".to_string()],
+            vec!["e5f6".to_string(), "Eyes".to_string(), "".to_string()]
+        ];
+        assert_eq!(table(&["ID", "NAME", "ACTIVE"], rows), concat!(
+            "ID        NAME     ACTIVE\n",
+            "a1b2c3d4  Stretch  yes\n",
+            "e5f6      Eyes\n"
+        ));
+    }
+}
@@ -0,0 +1,18 @@
+// the control socket is a unix domain socket, so the cli only exists on unix
+#[cfg(unix)]
+mod cli;
+#[cfg(unix)]
+mod client;
+#[cfg(unix)]
+mod format;
+
+#[cfg(unix)]
+fn main() {
+    cli::main()
+}
+
+#[cfg(not(unix))]
+fn main() {
+    eprintln!("Error: pazctl is only supported on unix");
+    std::process::exit(1);
+}
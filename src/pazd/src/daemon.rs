@@ -0,0 +1,204 @@
+use clap::Parser;
+use fs2::FileExt;
+use pazcore::{ClientCommand, Core, DATA_DIR_NAME, ipc};
+use std::{fs::{self, File, OpenOptions}, io::{self, Write}, os::unix::io::AsRawFd, path::{Path, PathBuf}};
+use tokio::signal::unix::{signal, SignalKind};
+use tracing::{error, info, warn};
+
+static PID_FILE_NAME: &str = "pazd.pid";
+static LOG_FILE_NAME: &str = "pazd.log";
+
+#[derive(Parser)]
+#[clap(name = "pazd", version, about = "Run the paz reminder engine as a headless daemon")]
+struct Cli {
+    /// Directory paz keeps its data in, defaults to the platform's data directory
+    #[clap(long)]
+    data_dir: Option<PathBuf>,
+
+    /// File to append panics and other stderr output to, defaults to pazd.log in the paz data directory,
+    /// logs are kept in the rotating files of its logs directory
+    #[clap(long)]
+    log_file: Option<PathBuf>,
+
+    /// Keep logging to stdout and leave stderr alone, e.g. when running in the foreground
+    #[clap(long)]
+    stdout: bool
+}
+
+#[tokio::main]
+pub async fn main() {
+    let cli = Cli::parse();
+    let data_dir = cli.data_dir.unwrap_or_else(|| dirs::data_dir().unwrap_or_else(|| PathBuf::from("./")));
+    let paz_dir = data_dir.join(DATA_DIR_NAME);
+    fs::create_dir_all(&paz_dir).unwrap();
+
+    // refuse to run twice against the same data directory
+    let _pid_file = match PidFile::acquire(paz_dir.join(PID_FILE_NAME)) {
+        Ok(p) => p,
+        Err(e) => {
+            eprintln!("Error: pazd: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    if !cli.stdout {
+        let log_path = cli.log_file.unwrap_or_else(|| paz_dir.join(LOG_FILE_NAME));
+        if let Err(e) = redirect_output(&log_path) {
+            eprintln!("Error: pazd: cannot log to {}: {}", log_path.display(), e);
+            std::process::exit(1);
+        }
+    }
+
+    run(data_dir).await;
+}
+
+async fn run(data_dir: PathBuf) {
+    // the core sets up logging, log lines before it are lost
+    let mut core = Core::new(data_dir.clone());
+    info!("starting with pid {}", std::process::id());
+    let controller = core.get_controller();
+    core.initialize();
+
+    let mut core_task = tokio::spawn(async move {
+        core.start().await;
+    });
+
+    let socket = ipc::socket_path(&data_dir);
+    let ipc_controller = controller.clone();
+    let ipc_socket = socket.clone();
+    tokio::spawn(async move {
+        if let Err(e) = ipc::serve(ipc_socket, ipc_controller).await {
+            error!("ipc: {:?}", e);
+        }
+    });
+
+    // only listens when enabled in the client state
+    tokio::spawn(pazcore::http::start(controller.clone()));
+
+    let mut terminate = signal(SignalKind::terminate()).unwrap();
+    let mut interrupt = signal(SignalKind::interrupt()).unwrap();
+    let mut hangup = signal(SignalKind::hangup()).unwrap();
+    loop {
+        tokio::select! {
+            _ = terminate.recv() => {
+                info!("SIGTERM received, shutting down");
+                break;
+            },
+            _ = interrupt.recv() => {
+                info!("SIGINT received, shutting down");
+                break;
+            },
+            // e.g. a Shutdown command sent over the control socket
+            _ = &mut core_task => {
+                info!("core stopped, exiting");
+                fs::remove_file(&socket).unwrap_or(());
+                return
+            },
+            _ = hangup.recv() => {
+                info!("SIGHUP received, reloading client state");
+                // an invalid file is rejected by the core, which keeps running with the current state
+                if let Err(e) = controller.command(ClientCommand::ReloadState).await {
+                    error!("reload failed, keeping the current state: {}", e);
+                }
+            }
+        }
+    }
+
+    // stop reminders and flush state before the process goes away
+    if let Err(e) = controller.shutdown().await {
+        error!("shutdown failed: {}", e);
+    }
+    if tokio::time::timeout(std::time::Duration::from_secs(10), core_task).await.is_err() {
+        warn!("core did not stop in time");
+    }
+
+    fs::remove_file(&socket).unwrap_or(());
+}
+
+// an exclusively locked file holding the daemon's pid, emptied again on drop
+struct PidFile {
+    file: File
+}
+
+impl PidFile {
+    fn acquire(path: PathBuf) -> io::Result<PidFile> {
+        // truncated only once the lock is held, the pid belongs to whoever holds it
+        let mut file = OpenOptions::new().create(true).read(true).write(true).truncate(false).open(&path)?;
+        if file.try_lock_exclusive().is_err() {
+            let pid = fs::read_to_string(&path).unwrap_or_default();
+            return Err(io::Error::new(
+                io::ErrorKind::WouldBlock,
+                format!("pazd is already running with pid {} ({})", pid.trim(), path.display())
+            ));
+        }
+        file.set_len(0)?;
+        writeln!(file, "{}", std::process::id())?;
+        Ok(PidFile { file })
+    }
+}
+
+impl Drop for PidFile {
+    // the file is kept, unlinking it while locked would let a second daemon lock a new file at the same path
+    fn drop(&mut self) {
+        self.file.set_len(0).unwrap_or(());
+    }
+}
+
+// the core's logger already writes its own rotating files and copies every line to stdout,
+// so stdout is dropped and only stderr, e.g. panics, is kept in the log file
+fn redirect_output(path: &Path) -> io::Result<()> {
+    let null = OpenOptions::new().write(true).open("/dev/null")?;
+    let file = OpenOptions::new().create(true).append(true).open(path)?;
+    for (fd, target) in [(null.as_raw_fd(), libc::STDOUT_FILENO), (file.as_raw_fd(), libc::STDERR_FILENO)] {
+        // SAFETY: both descriptors are valid for the duration of the call
+        if unsafe { libc::dup2(fd, target) } == -1 {
+            return Err(io::Error::last_os_error())
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, io, path::PathBuf};
+    use super::PidFile;
+
+    fn pid_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("pazd-{}-{}.pid", std::process::id(), name))
+    }
+
+    #[test]
+    fn pid_file_is_locked_while_held() {
+        let path = pid_path("locked");
+        let held = PidFile::acquire(path.clone()).unwrap();
+        let pid = fs::read_to_string(&path).unwrap();
+        let second = PidFile::acquire(path.clone());
+        drop(held);
+
+        assert_eq!(pid.trim(), std::process::id().to_string());
+        match second {
+            Err(e) => assert_eq!(e.kind(), io::ErrorKind::WouldBlock),
+            Ok(_) => panic!("the pid file was acquired twice")
+        }
+        assert_eq!(fs::read_to_string(&path).unwrap(), "");
+
+        // released, the next daemon takes the same file
+        let next = PidFile::acquire(path.clone()).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap().trim(), std::process::id().to_string());
+        drop(next);
+        fs::remove_file(&path).unwrap_or(());
+    }
+
+    #[test]
+    fn stale_pid_file_is_taken_over() {
+        let path = pid_path("stale");
+        fs::write(&path, "999999\n").unwrap();
+        let held = PidFile::acquire(path.clone()).unwrap();
+        let pid = fs::read_to_string(&path).unwrap();
+        drop(held);
+
+        assert_eq!(pid.trim(), std::process::id().to_string());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "");
+        fs::remove_file(&path).unwrap_or(());
+    }
+}
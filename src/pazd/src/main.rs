@@ -0,0 +1,14 @@
+// signals, the pid file lock and the control socket are unix only
+#[cfg(unix)]
+mod daemon;
+
+#[cfg(unix)]
+fn main() {
+    daemon::main()
+}
+
+#[cfg(not(unix))]
+fn main() {
+    eprintln!("Error: pazd is only supported on unix");
+    std::process::exit(1);
+}